@@ -4,6 +4,7 @@ pub mod draw;
 pub mod events;
 pub mod external;
 pub mod gm_save;
+pub mod icon;
 pub mod includedfile;
 pub mod model;
 pub mod movement;
@@ -33,13 +34,13 @@ use crate::{
         Object, Script, Sound, Timeline,
     },
     game::gm_save::GMSave,
-    gml::{self, ds, ev, file, rand::Random, runtime::Instruction, Compiler, Context},
+    gml::{self, ds, ev, file, mplay, rand::Random, runtime::Instruction, Compiler, Context},
     handleman::{HandleArray, HandleList},
     input::{self, Input},
     instance::{DummyFieldHolder, Instance, InstanceState},
     instancelist::{InstanceList, TileList},
     math::Real,
-    render::{atlas::AtlasBuilder, Renderer, RendererOptions, Scaling},
+    render::{atlas::AtlasBuilder, Backend, Renderer, RendererOptions, Scaling},
     tile,
     types::{Colour, ID},
     util,
@@ -116,6 +117,8 @@ pub struct Game {
     pub mpgrids: HandleList<pathfinding::MpGrid>,
     pub ds_precision: Real,
 
+    pub mplay: mplay::Multiplayer,
+
     pub default_font: Font,
     pub draw_font_id: ID,
     pub draw_colour: Colour,
@@ -135,6 +138,7 @@ pub struct Game {
 
     pub fps: u32,                 // initially 0
     pub frame_counter: u32,       // for FPS - gets set to 0 about once per second
+    pub step_count: u64,          // number of steps run since the game started
     pub transition_kind: i32,     // default 0
     pub transition_steps: i32,    // default 80
     pub cursor_sprite: i32,       // default -1
@@ -166,23 +170,28 @@ pub struct Game {
     pub encoding: &'static Encoding,
 
     pub esc_close_game: bool,
+    pub freeze_on_lose_focus: bool,
 
     pub play_type: PlayType,
     pub stored_events: VecDeque<replay::Event>,
-    pub frame_limiter: bool, // whether to limit FPS of gameplay by room_speed
+    pub frame_limiter: bool,  // whether to limit FPS of gameplay by room_speed
+    pub fps_cap: Option<u32>, // if set, the frame limiter never runs faster than this
 
     pub audio: audio::AudioManager,
 
     // winit windowing
-    pub window: Window,
+    pub window: Option<Window>,
+    pub window_icon_handles: Option<icon::WindowIcons>, // dropped after the window, which uses them
     pub window_border: bool,
-    pub window_caption: String,
+    pub window_caption: gml::String,
     pub window_cursor_gml: i32,
+    pub window_has_focus: bool,
     pub window_icons: bool,
     pub window_inner_size: (u32, u32),
     pub window_offset_spoof: (i32, i32),
     pub window_is_logical_dpi: bool,
     pub window_sizeable: bool,
+    pub window_title: String, // last title given to the window by the room caption
     pub window_visible: bool,
     pub close_requested: bool,
     // Scaling type
@@ -286,6 +295,7 @@ impl Game {
         encoding: &'static Encoding,
         frame_limiter: bool,
         play_type: PlayType,
+        headless: bool,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         // Parse file path
         let mut file_path2 = file_path.clone();
@@ -324,6 +334,7 @@ impl Game {
             constants,
             extensions,
             fonts,
+            ico_file_raw,
             included_files,
             last_instance_id,
             last_tile_id,
@@ -543,32 +554,46 @@ impl Game {
         let (width, height) = options.size;
         let window_border = !settings.dont_draw_border;
         let window_icons = !settings.dont_show_buttons;
-        let window = Window::builder()
-            .visible(false)
-            .inner_size(Size::Physical(width.into(), height.into()))
-            .borderless(!window_border && play_type != PlayType::Record)
-            .title(room1_caption.to_owned())
-            .resizable(match play_type {
-                PlayType::Normal => settings.allow_resize,
-                PlayType::Record => true,
-                PlayType::Replay => false,
-            })
-            .controls(if play_type == PlayType::Record {
-                Some(Controls::enabled())
-            } else if window_icons {
-                Some(Controls::new(settings.allow_resize, settings.allow_resize, true))
-            } else {
-                None
-            })
-            .build()
-            .expect("oh no");
+        // Headless runs don't get a window at all
+        let (window, window_icon_handles) = if headless {
+            (None, None)
+        } else {
+            let window = Window::builder()
+                .visible(false)
+                .inner_size(Size::Physical(width.into(), height.into()))
+                .borderless(!window_border && play_type != PlayType::Record)
+                .title(room1_caption.to_owned())
+                .resizable(match play_type {
+                    PlayType::Normal => settings.allow_resize,
+                    PlayType::Record => true,
+                    PlayType::Replay => false,
+                })
+                .controls(if play_type == PlayType::Record {
+                    Some(Controls::enabled())
+                } else if window_icons {
+                    Some(Controls::new(settings.allow_resize, settings.allow_resize, true))
+                } else {
+                    None
+                })
+                .build()
+                .expect("oh no");
+            let icons = ico_file_raw.as_ref().map(|ico| icon::set_window_icon(&window, ico));
+            (Some(window), icons)
+        };
 
         // Set up audio manager
-        let mut audio = audio::AudioManager::new(play_type != PlayType::Record);
+        let mut audio = audio::AudioManager::new(play_type != PlayType::Record && !headless);
 
         // TODO: specific flags here (make wb mutable)
 
-        let mut renderer = Renderer::new((), &options, &window, settings.clear_colour.into())?;
+        let mut renderer = Renderer::new(
+            match &window {
+                Some(window) => Backend::OpenGL(window),
+                None => Backend::Null,
+            },
+            &options,
+            settings.clear_colour.into(),
+        )?;
 
         let mut atlases = AtlasBuilder::new(renderer.max_texture_size() as _);
 
@@ -1233,6 +1258,7 @@ impl Game {
             grids: HandleList::new(),
             mpgrids: HandleList::new(),
             ds_precision: Real::from(0.00000001),
+            mplay: Default::default(),
             default_font,
             draw_font_id: -1,
             draw_colour: Colour::new(0.0, 0.0, 0.0),
@@ -1270,11 +1296,14 @@ impl Game {
             file_finder: None,
             spoofed_time_nanos: None,
             frame_limiter,
+            fps_cap: None,
             fps: 0,
             frame_counter: 0,
+            step_count: 0,
             parameters: game_arguments,
             encoding,
             esc_close_game: settings.esc_close_game,
+            freeze_on_lose_focus: settings.freeze_on_lose_focus,
             score_capt_d: true,
             has_set_show_score: false,
             lives_capt_d: false,
@@ -1283,6 +1312,7 @@ impl Game {
             error_last: "".to_string().into(),
             audio,
             window,
+            window_icon_handles,
             window_border,
             window_icons,
             close_requested: false,
@@ -1295,12 +1325,14 @@ impl Game {
             unscaled_height: 0,
 
             // lazy state
-            window_caption: room1_caption.clone(),
+            window_caption: room1_caption.clone().into(),
             window_cursor_gml: gml::mappings::constants::CR_DEFAULT as _,
+            window_has_focus: true,
             window_inner_size: (width, height),
             window_is_logical_dpi: false,
             window_offset_spoof: (0, 0),
             window_sizeable: settings.allow_resize,
+            window_title: String::new(),
             window_visible: true,
         };
 
@@ -1338,7 +1370,9 @@ impl Game {
         game.globals.vars.clear();
         game.globalvars.clear();
 
-        game.window.set_visible(true);
+        if let Some(window) = &mut game.window {
+            window.set_visible(true);
+        }
 
         Ok(game)
     }
@@ -1437,7 +1471,9 @@ impl Game {
             };
             if self.play_type != PlayType::Record {
                 self.window_inner_size = (width, height);
-                self.window.set_inner_size(Size::Physical(width, height));
+                if let Some(window) = &mut self.window {
+                    window.set_inner_size(Size::Physical(width, height));
+                }
             }
         }
     }
@@ -1774,6 +1810,8 @@ impl Game {
             return Ok(())
         }
 
+        self.step_count += 1;
+
         // Update xprevious and yprevious for all instances
         let mut iter = self.room.instance_list.iter_by_drawing();
         while let Some(instance) = iter.next(&self.room.instance_list).map(|x| self.room.instance_list.get(x)) {
@@ -1961,27 +1999,51 @@ impl Game {
 
     pub fn process_window_events(&mut self) {
         self.input.mouse_step();
-        self.window.swap_events();
+        // These come from the game itself rather than the window, so they're applied in every play type
+        self.input.apply_queued_keys();
+        let window = match &mut self.window {
+            Some(window) => window,
+            None => return,
+        };
+        window.swap_events();
         match self.play_type {
             PlayType::Normal => {
-                for event in self.window.events() {
+                for event in window.events() {
                     match event {
                         Event::KeyboardDown(key) => self.input.button_press(input::ramen2vk(*key), true),
                         Event::KeyboardUp(key) => self.input.button_release(input::ramen2vk(*key), true),
                         Event::MouseMove((point, scale)) => {
                             let (x, y) = point.as_physical(*scale);
                             if let (Ok(x), Ok(y)) = (i32::try_from(x), i32::try_from(y)) {
-                                self.input.mouse_move_to((x, y));
+                                // Undo any letterboxing so the position lines up with the framebuffer
+                                let (window_w, window_h) = self.window_inner_size;
+                                let pos = self.scaling.window_to_framebuffer(
+                                    x,
+                                    y,
+                                    self.unscaled_width as i32,
+                                    self.unscaled_height as i32,
+                                    window_w as i32,
+                                    window_h as i32,
+                                );
+                                self.input.mouse_move_to_scaled(pos, (x, y));
                             }
                         },
                         Event::MouseDown(button) => self.input.mouse_press(input::ramen2mb(*button), true),
                         Event::MouseUp(button) => self.input.mouse_release(input::ramen2mb(*button), true),
                         Event::MouseWheel(x) => self.input.mouse_scroll(*x),
                         Event::Resize((size, scale)) => self.window_inner_size = size.as_physical(*scale),
+                        Event::Focus(focus) => {
+                            // Keys released while unfocused are never seen, so treat them all as released now
+                            if !*focus {
+                                self.input.button_release_all();
+                            }
+                            self.window_has_focus = *focus;
+                        },
                         Event::CloseRequest(_) => self.close_requested = true,
                         _ => (),
                     }
                 }
+                self.input.poll_joysticks();
             },
             _ => (),
         }
@@ -2059,6 +2121,23 @@ impl Game {
         }
     }
 
+    /// Gets the number of steps the game has run since it started.
+    pub fn step_count(&self) -> u64 {
+        self.step_count
+    }
+
+    /// Gets how long the frame limiter should make each frame last, or None if frames aren't being limited.
+    /// Vsync only adds its own wait when swapping buffers, so it never lets frames run faster than this.
+    /// This only affects real time - spoofed time always advances according to room_speed.
+    pub fn frame_limit(&self) -> Option<Duration> {
+        if self.frame_limiter {
+            let fps = self.fps_cap.map_or(self.room.speed, |cap| cap.min(self.room.speed)).max(1);
+            Some(Duration::new(0, 1_000_000_000u32 / fps))
+        } else {
+            None
+        }
+    }
+
     // Plays the game normally
     pub fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         self.init()?;
@@ -2069,6 +2148,14 @@ impl Game {
         loop {
             self.process_window_events();
 
+            // Don't run any frames while unfocused if the game is set to freeze
+            if self.freeze_on_lose_focus && !self.window_has_focus && !self.close_requested {
+                // Still wait a little when frames aren't limited, so this doesn't spin
+                gml::datetime::sleep(self.frame_limit().unwrap_or(Duration::from_millis(10)));
+                time_now = Instant::now();
+                continue
+            }
+
             self.frame()?;
             handle_scene_change!(self);
 
@@ -2093,13 +2180,51 @@ impl Game {
             }
             self.frame_counter += 1;
 
-            if let (Some(time), true) = (duration.checked_sub(diff), self.frame_limiter) {
-                gml::datetime::sleep(time);
-                time_now += duration;
-            } else {
-                time_now = Instant::now();
+            match self.frame_limit() {
+                Some(limit) if limit > diff => {
+                    gml::datetime::sleep(limit - diff);
+                    time_now += limit;
+                },
+                _ => time_now = Instant::now(),
+            }
+        }
+    }
+
+    // Runs the game for a fixed number of frames without any window, as fast as possible
+    // Each frame is added to the replay with no inputs, so a savestate made afterwards can be loaded and continued
+    pub fn run_headless(&mut self, frames: usize, replay: &mut Replay) -> Result<(), Box<dyn std::error::Error>> {
+        // same hotfix as in replay(), so sprite and atlas ids line up with the tas ui
+        for _ in 0..2 {
+            self.renderer.upload_sprite(Box::new([0, 0, 0, 0]), 1, 1, 0, 0).expect("Failed to upload blank sprite");
+        }
+
+        self.init()?;
+        handle_scene_change!(self);
+
+        for _ in 0..frames {
+            replay.new_frame();
+            self.input.mouse_step();
+            self.input.apply_queued_keys();
+
+            self.frame()?;
+            handle_scene_change!(self);
+
+            // exit if game_end() invoked
+            if self.close_requested {
+                return Ok(self.run_game_end_events()?)
+            }
+
+            if let Some(t) = self.spoofed_time_nanos.as_mut() {
+                *t += Duration::new(0, 1_000_000_000u32 / self.room.speed.max(1)).as_nanos();
             }
+
+            if self.frame_counter == self.room.speed {
+                self.fps = self.room.speed;
+                self.frame_counter = 0;
+            }
+            self.frame_counter += 1;
         }
+        Ok(())
     }
 
     // Replays some recorded inputs to the game
@@ -2122,8 +2247,11 @@ impl Game {
 
         let mut time_now = Instant::now();
         loop {
-            self.window.swap_events();
+            if let Some(window) = &mut self.window {
+                window.swap_events();
+            }
             self.input.mouse_step();
+            self.input.apply_queued_keys();
             if let Some(frame) = replay.get_frame(frame_count) {
                 if !self.stored_events.is_empty() {
                     return Err(format!(
@@ -2188,11 +2316,12 @@ impl Game {
             }
             self.frame_counter += 1;
 
-            if let (Some(time), true) = (duration.checked_sub(diff), self.frame_limiter) {
-                gml::datetime::sleep(time);
-                time_now += duration;
-            } else {
-                time_now = Instant::now();
+            match self.frame_limit() {
+                Some(limit) if limit > diff => {
+                    gml::datetime::sleep(limit - diff);
+                    time_now += limit;
+                },
+                _ => time_now = Instant::now(),
             }
 
             frame_count += 1;
@@ -221,10 +221,14 @@ impl Game {
             self.unscaled_height as _,
         );
 
-        // Apply room caption
+        // Apply room caption, but only when it changes so window_set_caption isn't overwritten
         let title = self.get_window_title();
-        if self.play_type != PlayType::Record {
-            self.window.set_title(title.as_ref());
+        if title != self.window_title.as_str() {
+            let title = title.into_owned();
+            if let (Some(window), true) = (&self.window, self.play_type != PlayType::Record) {
+                window.set_title(&title);
+            }
+            self.window_title = title;
         }
 
         Ok(())
@@ -0,0 +1,169 @@
+use ramen::window::Window;
+use std::convert::TryInto;
+
+/// Finds the image in an .ico file which best fits the given size, returning its raw data.
+/// Prefers an exact match, then the smallest larger image, then the largest smaller image.
+/// Between images of the same size, the one with the most colours wins.
+pub fn find_icon(ico: &[u8], size: u32) -> Option<&[u8]> {
+    let count = usize::from(u16::from_le_bytes(ico.get(4..6)?.try_into().ok()?));
+    let mut best: Option<(u32, u16, &[u8])> = None;
+    for entry in (0..count).filter_map(|i| ico.get(6 + i * 16..6 + (i + 1) * 16)) {
+        // a width of 0 means 256
+        let width = if entry[0] == 0 { 256 } else { u32::from(entry[0]) };
+        let len = u32::from_le_bytes(entry[8..12].try_into().unwrap()) as usize;
+        let offset = u32::from_le_bytes(entry[12..16].try_into().unwrap()) as usize;
+        let data = match offset.checked_add(len).and_then(|end| ico.get(offset..end)) {
+            Some(data) => data,
+            None => continue,
+        };
+        // the bit count is often left as 0, in which case it's in the image's own header
+        let bpp = match u16::from_le_bytes(entry[6..8].try_into().unwrap()) {
+            0 if data.starts_with(b"\x89PNG") => 32,
+            0 => data.get(14..16).map_or(0, |b| u16::from_le_bytes(b.try_into().unwrap())),
+            bpp => bpp,
+        };
+        let better = match best {
+            None => true,
+            Some((best_width, best_bpp, _)) if best_width == width => bpp > best_bpp,
+            Some((best_width, ..)) if best_width < size => width > best_width,
+            Some((best_width, ..)) => width >= size && width < best_width,
+        };
+        if better {
+            best = Some((width, bpp, data));
+        }
+    }
+    best.map(|(.., data)| data)
+}
+
+/// Icons given to a window by set_window_icon. They're destroyed when this is dropped,
+/// so it should be kept alive for as long as the window is.
+pub struct WindowIcons {
+    #[cfg(target_os = "windows")]
+    handles: Vec<win32::HICON>,
+}
+
+#[cfg(target_os = "windows")]
+mod win32 {
+    pub type HICON = *mut std::os::raw::c_void;
+
+    #[link(name = "User32")]
+    extern "system" {
+        pub fn DestroyIcon(hicon: HICON) -> i32;
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for WindowIcons {
+    fn drop(&mut self) {
+        for &icon in &self.handles {
+            unsafe {
+                win32::DestroyIcon(icon);
+            }
+        }
+    }
+}
+
+/// Sets the window's small and large icons from an .ico file. Does nothing if the file has no usable images.
+#[cfg(target_os = "windows")]
+pub fn set_window_icon(window: &Window, ico: &[u8]) -> WindowIcons {
+    use ramen::platform::win32::{HWND, WindowExt as _};
+
+    const SM_CXICON: i32 = 11;
+    const SM_CXSMICON: i32 = 49;
+    const WM_SETICON: u32 = 0x0080;
+    const ICON_SMALL: usize = 0;
+    const ICON_BIG: usize = 1;
+
+    #[link(name = "User32")]
+    extern "system" {
+        fn CreateIconFromResourceEx(
+            presbits: *const u8,
+            dwressize: u32,
+            ficon: i32,
+            dwver: u32,
+            cxdesired: i32,
+            cydesired: i32,
+            flags: u32,
+        ) -> win32::HICON;
+        fn GetSystemMetrics(nindex: i32) -> i32;
+        fn SendMessageW(hwnd: HWND, msg: u32, wparam: usize, lparam: isize) -> isize;
+    }
+
+    let mut icons = WindowIcons { handles: Vec::new() };
+    for &(metric, kind) in &[(SM_CXSMICON, ICON_SMALL), (SM_CXICON, ICON_BIG)] {
+        unsafe {
+            let size = GetSystemMetrics(metric);
+            if let Some(data) = find_icon(ico, size as u32) {
+                let icon = CreateIconFromResourceEx(data.as_ptr(), data.len() as u32, 1, 0x00030000, size, size, 0);
+                if !icon.is_null() {
+                    SendMessageW(window.hwnd(), WM_SETICON, kind, icon as isize);
+                    icons.handles.push(icon);
+                }
+            }
+        }
+    }
+    icons
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn set_window_icon(_window: &Window, _ico: &[u8]) -> WindowIcons {
+    // TODO: ramen doesn't have a way to set icons on other platforms yet
+    WindowIcons {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_icon;
+
+    // builds an .ico file from (width, bpp, image data) entries
+    fn build_ico(images: &[(u8, u16, &[u8])]) -> Vec<u8> {
+        let mut ico = vec![0, 0, 1, 0, images.len() as u8, 0];
+        let mut offset = 6 + images.len() * 16;
+        for &(width, bpp, data) in images {
+            ico.extend_from_slice(&[width, width, 0, 0, 1, 0]);
+            ico.extend_from_slice(&bpp.to_le_bytes());
+            ico.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            ico.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += data.len();
+        }
+        for &(_, _, data) in images {
+            ico.extend_from_slice(data);
+        }
+        ico
+    }
+
+    #[test]
+    fn size() {
+        let ico = build_ico(&[(16, 32, b"16"), (32, 32, b"32"), (0, 32, b"256")]);
+        assert_eq!(find_icon(&ico, 16), Some(&b"16"[..]));
+        assert_eq!(find_icon(&ico, 24), Some(&b"32"[..]));
+        assert_eq!(find_icon(&ico, 48), Some(&b"256"[..]));
+        assert_eq!(find_icon(&ico, 512), Some(&b"256"[..]));
+        assert_eq!(find_icon(&ico, 8), Some(&b"16"[..]));
+    }
+
+    #[test]
+    fn depth() {
+        let ico = build_ico(&[(32, 4, b"4bpp"), (32, 32, b"32bpp"), (32, 8, b"8bpp"), (16, 32, b"16")]);
+        assert_eq!(find_icon(&ico, 32), Some(&b"32bpp"[..]));
+        assert_eq!(find_icon(&ico, 24), Some(&b"32bpp"[..]));
+
+        // bit count missing from the directory, so it's read from the bitmap or png header
+        let mut bmp4 = [0; 16];
+        bmp4[14] = 4;
+        let mut bmp32 = [1; 16];
+        bmp32[14..16].copy_from_slice(&32u16.to_le_bytes());
+        let ico = build_ico(&[(32, 0, &bmp4[..]), (32, 0, &bmp32[..])]);
+        assert_eq!(find_icon(&ico, 32), Some(&bmp32[..]));
+        let ico = build_ico(&[(32, 0, &bmp4[..]), (32, 0, &b"\x89PNG"[..])]);
+        assert_eq!(find_icon(&ico, 32), Some(&b"\x89PNG"[..]));
+    }
+
+    #[test]
+    fn truncated() {
+        assert_eq!(find_icon(&[], 32), None);
+        let mut ico = build_ico(&[(32, 32, b"32")]);
+        ico.pop();
+        assert_eq!(find_icon(&ico, 32), None);
+    }
+}
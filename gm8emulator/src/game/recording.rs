@@ -398,7 +398,9 @@ impl Game {
             }
         }
 
-        self.window.set_inner_size(Size::Physical(config.ui_width.into(), config.ui_height.into()));
+        // The TAS UI is never headless, so there's always a window here
+        let window = self.window.as_mut().unwrap();
+        window.set_inner_size(Size::Physical(config.ui_width.into(), config.ui_height.into()));
 
         for (i, state) in keyboard_state.iter_mut().enumerate() {
             if self.input.keyboard_check_direct(i as u8) {
@@ -429,8 +431,9 @@ impl Game {
             io.set_mouse_wheel(0.0);
 
             // poll window events
-            self.window.swap_events();
-            for event in self.window.events() {
+            let window = self.window.as_mut().unwrap();
+            window.swap_events();
+            for event in window.events() {
                 match event {
                     ev @ Event::KeyboardDown(key) | ev @ Event::KeyboardUp(key) => {
                         setting_mouse_pos = false;
@@ -472,6 +475,7 @@ impl Game {
 
             // present imgui
             let fps_text = format!("FPS: {}", io.framerate().round());
+            let step_text = format!("Steps: {}", self.step_count());
             let win_frame_height = context.frame_height();
             let win_border_size = context.window_border_size();
             let win_padding = context.window_padding();
@@ -578,6 +582,7 @@ impl Game {
                 let frame = replay.new_frame();
 
                 self.input.mouse_step();
+                self.input.apply_queued_keys();
                 for (i, state) in keyboard_state.iter().enumerate() {
                     let i = i as u8;
                     match state {
@@ -830,6 +835,7 @@ impl Game {
                 frame.text(&seed_text);
             }
             frame.text(&rerecord_text);
+            frame.text(&step_text);
             frame.text(&fps_text);
 
             let keyboard_label = if config.full_keyboard {
@@ -968,6 +974,9 @@ impl Game {
                                     savestate::ReadError::DeserializeErr(err) => {
                                         format!("Error deserializing {}:\n\n{}", filename, err)
                                     },
+                                    savestate::ReadError::UnknownVersion(_) => {
+                                        format!("{} was made by a different version of the emulator", filename)
+                                    },
                                 });
                             },
                         }
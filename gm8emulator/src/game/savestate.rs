@@ -25,6 +25,10 @@ use std::{
     rc::Rc,
 };
 
+/// Savestate format version, stored at the start of each file. bincode has no way of telling whether a savestate
+/// was made with different fields, so this must be bumped whenever anything serialized into a SaveState changes.
+const VERSION: u32 = 1;
+
 /// Represents a savestate. Very similar to the Game struct, but without things which aren't serialized.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SaveState {
@@ -84,6 +88,7 @@ pub struct SaveState {
 
     pub fps: u32,
     pub frame_counter: u32,
+    pub step_count: u64,
     pub transition_kind: i32,
     pub transition_steps: i32,
     pub cursor_sprite: i32,
@@ -172,6 +177,7 @@ impl SaveState {
             potential_step_settings: game.potential_step_settings.clone(),
             fps: game.fps,
             frame_counter: game.frame_counter,
+            step_count: game.step_count,
             transition_kind: game.transition_kind.clone(),
             transition_steps: game.transition_steps.clone(),
             cursor_sprite: game.cursor_sprite.clone(),
@@ -274,6 +280,7 @@ impl SaveState {
         game.potential_step_settings = self.potential_step_settings;
         game.fps = self.fps;
         game.frame_counter = self.frame_counter;
+        game.step_count = self.step_count;
         game.transition_kind = self.transition_kind;
         game.transition_steps = self.transition_steps;
         game.cursor_sprite = self.cursor_sprite;
@@ -315,9 +322,13 @@ impl SaveState {
                 buffer.lz4_buf.reserve(init_size);
                 match file.read_to_end(&mut buffer.lz4_buf) {
                     Ok(_) => {
-                        match (buffer.lz4_buf.as_slice().read_u64::<LE>().map(|x| x as usize), buffer.lz4_buf.get(8..))
-                        {
-                            (Ok(len), Some(block)) => {
+                        let mut header = buffer.lz4_buf.as_slice();
+                        match (
+                            header.read_u32::<LE>(),
+                            header.read_u64::<LE>().map(|x| x as usize),
+                            buffer.lz4_buf.get(12..),
+                        ) {
+                            (Ok(VERSION), Ok(len), Some(block)) => {
                                 buffer.bin_buf.clear();
                                 buffer.bin_buf.reserve(len);
                                 unsafe { buffer.bin_buf.set_len(len) };
@@ -330,8 +341,11 @@ impl SaveState {
                                     Err(err) => Err(ReadError::DecompressErr(err)),
                                 }
                             },
-                            (Ok(_), None) => Err(ReadError::IOErr(io::Error::from(io::ErrorKind::UnexpectedEof))),
-                            (Err(err), _) => Err(ReadError::IOErr(err)),
+                            (Ok(VERSION), Ok(_), None) => {
+                                Err(ReadError::IOErr(io::Error::from(io::ErrorKind::UnexpectedEof)))
+                            },
+                            (Ok(VERSION), Err(err), _) | (Err(err), ..) => Err(ReadError::IOErr(err)),
+                            (Ok(v), ..) => Err(ReadError::UnknownVersion(v)),
                         }
                     },
                     Err(err) => Err(ReadError::IOErr(err)),
@@ -351,8 +365,10 @@ impl SaveState {
                 match lz4::compress_to_vec(buffer.bin_buf.as_slice(), buffer.lz4_buf.as_mut(), lz4::ACC_LEVEL_DEFAULT) {
                     Ok(_length) => {
                         match OpenOptions::new().create(true).write(true).truncate(true).open(path).and_then(|mut f| {
-                            f.write_u64::<LE>(buffer.bin_buf.len() as u64)
-                                .and_then(|_| f.write_all(buffer.lz4_buf.as_slice()))
+                            f.write_u32::<LE>(VERSION).and_then(|_| {
+                                f.write_u64::<LE>(buffer.bin_buf.len() as u64)
+                                    .and_then(|_| f.write_all(buffer.lz4_buf.as_slice()))
+                            })
                         }) {
                             Ok(()) => Ok(()),
                             Err(e) => Err(WriteError::IOErr(e)),
@@ -382,6 +398,7 @@ pub enum ReadError {
     IOErr(io::Error),
     DecompressErr(lzzzz::Error),
     DeserializeErr(Box<bincode::ErrorKind>),
+    UnknownVersion(u32),
 }
 
 #[derive(Debug)]
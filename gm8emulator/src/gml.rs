@@ -5,6 +5,7 @@ pub mod ds;
 pub mod file;
 pub mod kernel;
 pub mod mappings;
+pub mod mplay;
 pub mod network;
 pub mod rand;
 pub mod runtime;
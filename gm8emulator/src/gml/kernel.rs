@@ -162,7 +162,9 @@ impl Game {
 
     pub fn window_set_visible(&mut self, args: &[Value]) -> gml::Result<Value> {
         let visible = expect_args!(args, [bool])?;
-        self.window.set_visible(visible);
+        if let Some(window) = &mut self.window {
+            window.set_visible(visible);
+        }
         Ok(Default::default())
     }
 
@@ -205,10 +207,8 @@ impl Game {
         let show_icons = expect_args!(args, [bool])?;
         if show_icons != self.window_icons {
             self.window_icons = show_icons;
-            if self.play_type != PlayType::Record {
-                self.window.set_controls({
-                    if self.window_icons { Some(ramen::window::Controls::enabled()) } else { None }
-                })
+            if let (Some(window), true) = (&mut self.window, self.play_type != PlayType::Record) {
+                window.set_controls(if self.window_icons { Some(ramen::window::Controls::enabled()) } else { None })
             }
         }
         Ok(Default::default())
@@ -233,8 +233,8 @@ impl Game {
         let sizeable = expect_args!(args, [bool])?;
         if sizeable != self.window_sizeable {
             self.window_sizeable = sizeable;
-            if self.play_type != PlayType::Record {
-                self.window.set_resizable(self.window_sizeable);
+            if let (Some(window), true) = (&mut self.window, self.play_type != PlayType::Record) {
+                window.set_resizable(self.window_sizeable);
             }
         }
         Ok(Default::default())
@@ -246,11 +246,11 @@ impl Game {
     }
 
     pub fn window_set_caption(&mut self, args: &[Value]) -> gml::Result<Value> {
-        let caption = expect_args!(args, [string])?;
-        if self.play_type == PlayType::Record {
-            self.window.set_title(caption.as_ref());
+        let caption = expect_args!(args, [bytes])?;
+        if let (Some(window), true) = (&self.window, self.play_type != PlayType::Record) {
+            window.set_title(self.decode_str(caption.as_ref()).as_ref());
         }
-        self.window_caption = caption.into_owned();
+        self.window_caption = caption;
         Ok(Default::default())
     }
 
@@ -290,8 +290,8 @@ impl Game {
                 Cursor::Blank
             },
         };
-        if self.play_type == PlayType::Normal {
-            self.window.set_cursor(cursor);
+        if let (Some(window), PlayType::Normal) = (&mut self.window, self.play_type) {
+            window.set_cursor(cursor);
         }
         self.window_cursor_gml = code;
         Ok(Default::default())
@@ -323,21 +323,24 @@ impl Game {
         let (width, height) = expect_args!(args, [int, int])?;
         if width > 0 && height > 0 {
             self.window_inner_size = (width as u32, height as u32);
-            self.window.execute(|window| {
-                use ramen::monitor::Size;
-                if window.is_dpi_logical() {
-                    unimplemented!();
-                } else {
-                    window.set_inner_size(Size::Physical(width as u32, height as u32));
-                }
-            });
+            if let Some(window) = &mut self.window {
+                window.execute(|window| {
+                    use ramen::monitor::Size;
+                    if window.is_dpi_logical() {
+                        unimplemented!();
+                    } else {
+                        window.set_inner_size(Size::Physical(width as u32, height as u32));
+                    }
+                });
+            }
         }
         Ok(Default::default())
     }
 
-    pub fn window_set_rectangle(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 4
-        unimplemented!("Called unimplemented kernel function window_set_rectangle")
+    pub fn window_set_rectangle(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let (x, y, width, height) = expect_args!(args, [any, any, any, any])?;
+        self.window_set_position(&[x, y])?;
+        self.window_set_size(&[width, height])
     }
 
     pub fn window_center(&mut self, _args: &[Value]) -> gml::Result<Value> {
@@ -406,7 +409,9 @@ impl Game {
                 (region_w, region_h)
             };
             self.window_inner_size = (width, height);
-            self.window.set_inner_size(ramen::monitor::Size::Physical(width, height));
+            if let Some(window) = &mut self.window {
+                window.set_inner_size(ramen::monitor::Size::Physical(width, height));
+            }
         }
         Ok(Default::default())
     }
@@ -423,12 +428,12 @@ impl Game {
 
     pub fn window_mouse_get_x(&self, args: &[Value]) -> gml::Result<Value> {
         expect_args!(args, [])?;
-        Ok(self.input.mouse_x().into())
+        Ok(self.input.mouse_window_x().into())
     }
 
     pub fn window_mouse_get_y(&self, args: &[Value]) -> gml::Result<Value> {
         expect_args!(args, [])?;
-        Ok(self.input.mouse_y().into())
+        Ok(self.input.mouse_window_y().into())
     }
 
     pub fn window_mouse_set(&mut self, _args: &[Value]) -> gml::Result<Value> {
@@ -1999,9 +2004,22 @@ impl Game {
         Ok(self.last_tile_id.into())
     }
 
-    pub fn tile_find(&self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function tile_find")
+    pub fn tile_find(&self, args: &[Value]) -> gml::Result<Value> {
+        let (x, y, foreground) = expect_args!(args, [real, real, bool])?;
+        let use_scaling = self.gm_version == Version::GameMaker8_1; // 8.1 bugfix
+        let mut iter_tile = self.room.tile_list.iter_by_drawing();
+        while let Some(handle) = iter_tile.next(&self.room.tile_list) {
+            let tile = self.room.tile_list.get(handle);
+            if (tile.depth.get() < 0.into()) == foreground
+                && x >= tile.x.get()
+                && x < tile.x.get() + if use_scaling { tile.xscale.get() } else { 0.into() } * tile.width.get().into()
+                && y >= tile.y.get()
+                && y < tile.y.get() + if use_scaling { tile.yscale.get() } else { 0.into() } * tile.height.get().into()
+            {
+                return Ok(tile.id.get().into())
+            }
+        }
+        Ok((-1).into())
     }
 
     pub fn tile_exists(&self, args: &[Value]) -> gml::Result<Value> {
@@ -2019,9 +2037,17 @@ impl Game {
         }
     }
 
-    pub fn tile_delete_at(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function tile_delete_at")
+    pub fn tile_delete_at(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let (x, y, foreground) = expect_args!(args, [real, real, bool])?;
+        let use_scaling = self.gm_version == Version::GameMaker8_1; // 8.1 bugfix
+        self.room.tile_list.remove_with(|tile| {
+            (tile.depth.get() < 0.into()) == foreground
+                && x >= tile.x.get()
+                && x < tile.x.get() + if use_scaling { tile.xscale.get() } else { 0.into() } * tile.width.get().into()
+                && y >= tile.y.get()
+                && y < tile.y.get() + if use_scaling { tile.yscale.get() } else { 0.into() } * tile.height.get().into()
+        });
+        Ok(Default::default())
     }
 
     pub fn tile_layer_hide(&mut self, args: &[Value]) -> gml::Result<Value> {
@@ -2399,9 +2425,18 @@ impl Game {
         }
     }
 
-    pub fn surface_getpixel(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function surface_getpixel")
+    pub fn surface_getpixel(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let (surf_id, x, y) = expect_args!(args, [int, int, int])?;
+        if Some(surf_id) == self.surface_target {
+            self.renderer.flush_queue();
+        }
+        match self.surfaces.get_asset(surf_id) {
+            Some(surf) if x >= 0 && y >= 0 && x < surf.width as i32 && y < surf.height as i32 => {
+                let data = self.renderer.dump_sprite_part(surf.atlas_ref, x, y, 1, 1);
+                Ok(u32::from_le_bytes([data[0], data[1], data[2], 0]).into())
+            },
+            _ => Ok(Default::default()),
+        }
     }
 
     pub fn surface_copy(&mut self, args: &[Value]) -> gml::Result<Value> {
@@ -3509,7 +3544,9 @@ impl Game {
         } else {
             Cursor::Blank
         };
-        self.window.set_cursor(cursor);
+        if let Some(window) = &mut self.window {
+            window.set_cursor(cursor);
+        }
         Ok(Default::default())
     }
 
@@ -4035,12 +4072,12 @@ impl Game {
 
     pub fn string_copy(&self, args: &[Value]) -> gml::Result<Value> {
         let (s, start, len) = expect_args!(args, [bytes, int, int])?;
-        let start = (start - 1).max(0) as usize;
-        let len = len.max(0) as usize;
-        let s = s.as_ref();
         Ok(match self.gm_version {
-            Version::GameMaker8_0 => s.iter().skip(start).take(len).copied().collect::<Vec<_>>().into(),
-            Version::GameMaker8_1 => self.decode_str(s).chars().skip(start).take(len).collect::<String>().into(),
+            Version::GameMaker8_0 => gml::string::copy(s.as_ref(), start, len).into(),
+            Version::GameMaker8_1 => {
+                let chars = self.decode_str(s.as_ref()).chars().collect::<Vec<_>>();
+                gml::string::copy(&chars, start, len).iter().collect::<String>().into()
+            },
         })
     }
 
@@ -4048,35 +4085,23 @@ impl Game {
         let (string, pos) = expect_args!(args, [bytes, int])?;
         match self.gm_version {
             Version::GameMaker8_0 => {
-                Ok(string.as_ref().get((pos as isize - 1).max(0) as usize).map_or("".into(), |ch| vec![*ch].into()))
+                Ok(gml::string::char_at(string.as_ref(), pos).map_or("".into(), |ch| vec![*ch].into()))
+            },
+            Version::GameMaker8_1 => {
+                let chars = self.decode_str(string.as_ref()).chars().collect::<Vec<_>>();
+                Ok(gml::string::char_at(&chars, pos).map_or("".into(), |ch| ch.to_string().into()))
             },
-            Version::GameMaker8_1 => Ok(Value::Str(
-                self.decode_str(string.as_ref())
-                    .chars()
-                    .nth((pos as isize - 1).max(0) as usize)
-                    .map_or("".to_string().into(), |ch| ch.to_string().into()),
-            )),
         }
     }
 
     pub fn string_delete(&self, args: &[Value]) -> gml::Result<Value> {
         let (s, start, len) = expect_args!(args, [bytes, int, int])?;
-        let (start, len) = match (<usize>::try_from(start - 1), len) {
-            (Ok(a), b) if b > 0 => (a, b as usize),
-            _ => return Ok(s.into()),
-        };
-        let s = s.as_ref();
         Ok(match self.gm_version {
-            Version::GameMaker8_0 => {
-                s.iter().take(start).chain(s.iter().skip(start + len)).copied().collect::<Vec<_>>().into()
+            Version::GameMaker8_0 => gml::string::delete(s.as_ref(), start, len).into(),
+            Version::GameMaker8_1 => {
+                let chars = self.decode_str(s.as_ref()).chars().collect::<Vec<_>>();
+                gml::string::delete(&chars, start, len).into_iter().collect::<String>().into()
             },
-            Version::GameMaker8_1 => self
-                .decode_str(s)
-                .chars()
-                .enumerate()
-                .filter_map(|(i, x)| if (start..start + len).contains(&i) { None } else { Some(x) })
-                .collect::<String>()
-                .into(),
         })
     }
 
@@ -6929,22 +6954,21 @@ impl Game {
         Ok(Default::default())
     }
 
-    pub fn keyboard_key_press(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // let key = expect_args!(args, [int])?;
-        // if let Ok(vk) = u8::try_from(key) {
-        //     self.input.button_press(vk, true);
-        // }
-        // Ok(Default::default())
-        todo!() // should go on next event poll
+    pub fn keyboard_key_press(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let key = expect_args!(args, [int])?;
+        if let Ok(vk) = u8::try_from(key) {
+            // GM8 posts a key message here, so the press isn't seen until the next event poll
+            self.input.queue_key(vk, true);
+        }
+        Ok(Default::default())
     }
 
-    pub fn keyboard_key_release(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // let key = expect_args!(args, [int])?;
-        // if let Ok(vk) = u8::try_from(key) {
-        //     self.input.button_release(vk, true);
-        // }
-        // Ok(Default::default())
-        todo!() // should go on next event poll
+    pub fn keyboard_key_release(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let key = expect_args!(args, [int])?;
+        if let Ok(vk) = u8::try_from(key) {
+            self.input.queue_key(vk, false);
+        }
+        Ok(Default::default())
     }
 
     pub fn keyboard_set_map(&mut self, args: &[Value]) -> gml::Result<Value> {
@@ -7036,102 +7060,74 @@ impl Game {
         Ok(self.input.mouse_wheel_down().into())
     }
 
-    pub fn joystick_exists(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        //unimplemented!("Called unimplemented kernel function joystick_exists")
-        // TODO
-        Ok(gml::FALSE.into())
+    pub fn joystick_exists(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let id = expect_args!(args, [int])?;
+        Ok(self.input.joystick(id).is_some().into())
     }
 
-    pub fn joystick_direction(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        //unimplemented!("Called unimplemented kernel function joystick_direction")
-        // TODO
-        Ok(101.into())
+    pub fn joystick_direction(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let id = expect_args!(args, [int])?;
+        Ok(self.input.joystick(id).map_or(gml_consts::VK_NUMPAD5 as u8, |j| j.direction()).into())
     }
 
-    pub fn joystick_name(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        //unimplemented!("Called unimplemented kernel function joystick_name")
-        // TODO
-        Ok("".into())
+    pub fn joystick_name(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let id = expect_args!(args, [int])?;
+        Ok(self.input.joystick(id).map_or("", |j| j.name.as_str()).into())
     }
 
-    pub fn joystick_axes(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        //unimplemented!("Called unimplemented kernel function joystick_axes")
-        // TODO
-        Ok(0.into())
+    pub fn joystick_axes(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let id = expect_args!(args, [int])?;
+        Ok(self.input.joystick(id).map_or(0, |j| j.axis_count).into())
     }
 
-    pub fn joystick_buttons(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        //unimplemented!("Called unimplemented kernel function joystick_buttons")
-        // TODO
-        Ok(0.into())
+    pub fn joystick_buttons(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let id = expect_args!(args, [int])?;
+        Ok(self.input.joystick(id).map_or(0, |j| j.button_count).into())
     }
 
-    pub fn joystick_has_pov(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        //unimplemented!("Called unimplemented kernel function joystick_has_pov")
-        // TODO
-        Ok(gml::FALSE.into())
+    pub fn joystick_has_pov(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let id = expect_args!(args, [int])?;
+        Ok(self.input.joystick(id).map_or(false, |j| j.has_pov).into())
     }
 
-    pub fn joystick_check_button(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 2
-        //unimplemented!("Called unimplemented kernel function joystick_check_button")
-        // TODO
-        Ok(gml::FALSE.into())
+    pub fn joystick_check_button(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let (id, button) = expect_args!(args, [int, int])?;
+        Ok(self.input.joystick(id).map_or(false, |j| j.button(button)).into())
     }
 
-    pub fn joystick_xpos(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        //unimplemented!("Called unimplemented kernel function joystick_xpos")
-        // TODO
-        Ok(0.into())
+    pub fn joystick_xpos(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let id = expect_args!(args, [int])?;
+        Ok(self.input.joystick(id).map_or(0.0, |j| j.axis(0)).into())
     }
 
-    pub fn joystick_ypos(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        //unimplemented!("Called unimplemented kernel function joystick_ypos")
-        // TODO
-        Ok(0.into())
+    pub fn joystick_ypos(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let id = expect_args!(args, [int])?;
+        Ok(self.input.joystick(id).map_or(0.0, |j| j.axis(1)).into())
     }
 
-    pub fn joystick_zpos(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        //unimplemented!("Called unimplemented kernel function joystick_zpos")
-        // TODO
-        Ok(0.into())
+    pub fn joystick_zpos(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let id = expect_args!(args, [int])?;
+        Ok(self.input.joystick(id).map_or(0.0, |j| j.axis(2)).into())
     }
 
-    pub fn joystick_rpos(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        //unimplemented!("Called unimplemented kernel function joystick_rpos")
-        // TODO
-        Ok(0.into())
+    pub fn joystick_rpos(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let id = expect_args!(args, [int])?;
+        Ok(self.input.joystick(id).map_or(0.0, |j| j.axis(3)).into())
     }
 
-    pub fn joystick_upos(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        //unimplemented!("Called unimplemented kernel function joystick_upos")
-        // TODO
-        Ok(0.into())
+    pub fn joystick_upos(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let id = expect_args!(args, [int])?;
+        Ok(self.input.joystick(id).map_or(0.0, |j| j.axis(4)).into())
     }
 
-    pub fn joystick_vpos(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        //unimplemented!("Called unimplemented kernel function joystick_vpos")
-        // TODO
-        Ok(0.into())
+    pub fn joystick_vpos(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let id = expect_args!(args, [int])?;
+        Ok(self.input.joystick(id).map_or(0.0, |j| j.axis(5)).into())
     }
 
-    pub fn joystick_pov(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        //unimplemented!("Called unimplemented kernel function joystick_pov")
-        // TODO
-        Ok((-1).into())
+    pub fn joystick_pov(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let id = expect_args!(args, [int])?;
+        Ok(self.input.joystick(id).map_or(-1, |j| j.pov).into())
     }
 
     pub fn keyboard_clear(&mut self, args: &[Value]) -> gml::Result<Value> {
@@ -7155,7 +7151,7 @@ impl Game {
     pub fn io_clear(&mut self, args: &[Value]) -> gml::Result<Value> {
         expect_args!(args, [])?;
         self.process_window_events();
-        // TODO: clear keyboard_string
+        self.input.set_keyboard_string(b"");
         self.input.keyboard_clear_all();
         self.input.mouse_clear_all();
         Ok(Default::default())
@@ -7184,144 +7180,189 @@ impl Game {
         unimplemented!("Called unimplemented kernel function mouse_wait")
     }
 
-    pub fn mplay_init_ipx(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function mplay_init_ipx")
+    pub fn mplay_init_ipx(&mut self, args: &[Value]) -> gml::Result<Value> {
+        expect_args!(args, [])?;
+        // Only TCP/IP is supported
+        Ok(gml::FALSE.into())
     }
 
-    pub fn mplay_init_tcpip(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function mplay_init_tcpip")
+    pub fn mplay_init_tcpip(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let address = expect_args!(args, [string])?;
+        // Live network traffic can't be recorded or replayed, so multiplayer only works in normal play
+        if self.play_type != PlayType::Normal {
+            return Ok(gml::FALSE.into())
+        }
+        Ok(self.mplay.init_tcpip(address.as_ref()).into())
     }
 
-    pub fn mplay_init_modem(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function mplay_init_modem")
+    pub fn mplay_init_modem(&mut self, args: &[Value]) -> gml::Result<Value> {
+        expect_args!(args, [any, any])?;
+        Ok(gml::FALSE.into())
     }
 
-    pub fn mplay_init_serial(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 5
-        unimplemented!("Called unimplemented kernel function mplay_init_serial")
+    pub fn mplay_init_serial(&mut self, args: &[Value]) -> gml::Result<Value> {
+        expect_args!(args, [any, any, any, any, any])?;
+        Ok(gml::FALSE.into())
     }
 
-    pub fn mplay_connect_status(&self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function mplay_connect_status")
+    pub fn mplay_connect_status(&self, args: &[Value]) -> gml::Result<Value> {
+        expect_args!(args, [])?;
+        // 2 means TCP/IP
+        Ok(if self.mplay.is_connected() { 2 } else { 0 }.into())
     }
 
-    pub fn mplay_end(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function mplay_end")
+    pub fn mplay_end(&mut self, args: &[Value]) -> gml::Result<Value> {
+        expect_args!(args, [])?;
+        self.mplay.end();
+        Ok(Default::default())
     }
 
-    pub fn mplay_session_mode(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function mplay_session_mode")
+    pub fn mplay_session_mode(&mut self, args: &[Value]) -> gml::Result<Value> {
+        // There's no host migration, so the session always ends with its host
+        expect_args!(args, [any])?;
+        Ok(Default::default())
     }
 
-    pub fn mplay_session_create(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function mplay_session_create")
+    pub fn mplay_session_create(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let (name, max_players, player_name) = expect_args!(args, [string, int, string])?;
+        if self.play_type != PlayType::Normal {
+            return Ok(gml::FALSE.into())
+        }
+        let max_players = max_players.max(0) as usize;
+        Ok(self.mplay.create_session(name.as_ref(), max_players, player_name.as_ref()).is_ok().into())
     }
 
-    pub fn mplay_session_find(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function mplay_session_find")
+    pub fn mplay_session_find(&mut self, args: &[Value]) -> gml::Result<Value> {
+        expect_args!(args, [])?;
+        if self.play_type != PlayType::Normal {
+            return Ok(0.into())
+        }
+        Ok(self.mplay.find_sessions().into())
     }
 
-    pub fn mplay_session_name(&self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function mplay_session_name")
+    pub fn mplay_session_name(&self, args: &[Value]) -> gml::Result<Value> {
+        let index = expect_args!(args, [int])?;
+        Ok(usize::try_from(index).ok().and_then(|i| self.mplay.session_name(i)).unwrap_or("").into())
     }
 
-    pub fn mplay_session_join(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function mplay_session_join")
+    pub fn mplay_session_join(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let (index, player_name) = expect_args!(args, [int, string])?;
+        if self.play_type != PlayType::Normal {
+            return Ok(gml::FALSE.into())
+        }
+        match usize::try_from(index) {
+            Ok(index) => Ok(self.mplay.join_session(index, player_name.as_ref()).is_ok().into()),
+            Err(_) => Ok(gml::FALSE.into()),
+        }
     }
 
-    pub fn mplay_session_status(&self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function mplay_session_status")
+    pub fn mplay_session_status(&mut self, args: &[Value]) -> gml::Result<Value> {
+        expect_args!(args, [])?;
+        Ok(self.mplay.session_status().into())
     }
 
-    pub fn mplay_session_end(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function mplay_session_end")
+    pub fn mplay_session_end(&mut self, args: &[Value]) -> gml::Result<Value> {
+        expect_args!(args, [])?;
+        self.mplay.end_session();
+        Ok(Default::default())
     }
 
-    pub fn mplay_player_find(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function mplay_player_find")
+    pub fn mplay_player_find(&mut self, args: &[Value]) -> gml::Result<Value> {
+        expect_args!(args, [])?;
+        self.mplay.poll();
+        Ok(self.mplay.players().len().into())
     }
 
-    pub fn mplay_player_name(&self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function mplay_player_name")
+    pub fn mplay_player_name(&self, args: &[Value]) -> gml::Result<Value> {
+        let index = expect_args!(args, [int])?;
+        let player = usize::try_from(index).ok().and_then(|i| self.mplay.players().get(i));
+        Ok(player.map_or("", |p| p.name.as_str()).into())
     }
 
-    pub fn mplay_player_id(&self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function mplay_player_id")
+    pub fn mplay_player_id(&self, args: &[Value]) -> gml::Result<Value> {
+        let index = expect_args!(args, [int])?;
+        let player = usize::try_from(index).ok().and_then(|i| self.mplay.players().get(i));
+        Ok(player.map_or(0, |p| p.id).into())
     }
 
-    pub fn mplay_data_write(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function mplay_data_write")
+    pub fn mplay_data_write(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let (index, value) = expect_args!(args, [int, any])?;
+        if let Ok(index) = usize::try_from(index) {
+            self.mplay.data_write(index, value);
+        }
+        Ok(Default::default())
     }
 
-    pub fn mplay_data_read(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function mplay_data_read")
+    pub fn mplay_data_read(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let index = expect_args!(args, [int])?;
+        match usize::try_from(index) {
+            Ok(index) => Ok(self.mplay.data_read(index)),
+            Err(_) => Ok(Default::default()),
+        }
     }
 
-    pub fn mplay_data_mode(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function mplay_data_mode")
+    pub fn mplay_data_mode(&mut self, args: &[Value]) -> gml::Result<Value> {
+        // Everything goes over TCP, so it's always guaranteed
+        expect_args!(args, [any])?;
+        Ok(Default::default())
     }
 
-    pub fn mplay_message_send(&self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function mplay_message_send")
+    pub fn mplay_message_send(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let (player, id, value) = expect_args!(args, [any, int, any])?;
+        let sent = match self.mplay.resolve_player(&player) {
+            Some(player) => self.mplay.send_message(player, id, value),
+            None => false,
+        };
+        Ok(sent.into())
     }
 
-    pub fn mplay_message_send_guaranteed(&self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function mplay_message_send_guaranteed")
+    pub fn mplay_message_send_guaranteed(&mut self, args: &[Value]) -> gml::Result<Value> {
+        self.mplay_message_send(args)
     }
 
-    pub fn mplay_message_receive(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function mplay_message_receive")
+    pub fn mplay_message_receive(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let player = expect_args!(args, [any])?;
+        let received = match self.mplay.resolve_player(&player) {
+            Some(player) => self.mplay.receive_message(player),
+            None => false,
+        };
+        Ok(received.into())
     }
 
-    pub fn mplay_message_id(&self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function mplay_message_id")
+    pub fn mplay_message_id(&self, args: &[Value]) -> gml::Result<Value> {
+        expect_args!(args, [])?;
+        Ok(self.mplay.last_message().map_or(0, |(m, _)| m.id).into())
     }
 
-    pub fn mplay_message_value(&self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function mplay_message_value")
+    pub fn mplay_message_value(&self, args: &[Value]) -> gml::Result<Value> {
+        expect_args!(args, [])?;
+        Ok(self.mplay.last_message().map(|(m, _)| m.value.clone()).unwrap_or_default())
     }
 
-    pub fn mplay_message_player(&self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function mplay_message_player")
+    pub fn mplay_message_player(&self, args: &[Value]) -> gml::Result<Value> {
+        expect_args!(args, [])?;
+        Ok(self.mplay.last_message().map_or(0, |(m, _)| m.player).into())
     }
 
-    pub fn mplay_message_name(&self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function mplay_message_name")
+    pub fn mplay_message_name(&self, args: &[Value]) -> gml::Result<Value> {
+        expect_args!(args, [])?;
+        Ok(self.mplay.last_message().map_or("", |(_, name)| name.as_str()).into())
     }
 
-    pub fn mplay_message_count(&self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function mplay_message_count")
+    pub fn mplay_message_count(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let player = expect_args!(args, [any])?;
+        match self.mplay.resolve_player(&player) {
+            Some(player) => Ok(self.mplay.message_count(player).into()),
+            None => Ok(0.into()),
+        }
     }
 
-    pub fn mplay_message_clear(&mut self, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function mplay_message_clear")
+    pub fn mplay_message_clear(&mut self, args: &[Value]) -> gml::Result<Value> {
+        let player = expect_args!(args, [any])?;
+        if let Some(player) = self.mplay.resolve_player(&player) {
+            self.mplay.clear_messages(player);
+        }
+        Ok(Default::default())
     }
 
     pub fn mplay_ipaddress(&mut self, args: &[Value]) -> gml::Result<Value> {
@@ -7587,7 +7628,7 @@ impl Game {
         #[cfg(target_os = "windows")]
         {
             use ramen::platform::win32::WindowExt as _;
-            Ok((self.window.hwnd() as usize).into())
+            Ok(self.window.as_ref().map(|w| w.hwnd() as usize).unwrap_or(0).into())
         }
         // TODO: Others! (They'll compile error here so it'll remind me)
     }
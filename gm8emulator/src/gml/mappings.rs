@@ -1549,7 +1549,7 @@ pub const FUNCTIONS: phf::OrderedMap<&'static str, Function> = phf_ordered_map!
     "mplay_session_find" => Function::Engine(Game::mplay_session_find),
     "mplay_session_name" => Function::Constant(Game::mplay_session_name),
     "mplay_session_join" => Function::Engine(Game::mplay_session_join),
-    "mplay_session_status" => Function::Constant(Game::mplay_session_status),
+    "mplay_session_status" => Function::Engine(Game::mplay_session_status),
     "mplay_session_end" => Function::Engine(Game::mplay_session_end),
     "mplay_player_find" => Function::Engine(Game::mplay_player_find),
     "mplay_player_name" => Function::Constant(Game::mplay_player_name),
@@ -1557,14 +1557,14 @@ pub const FUNCTIONS: phf::OrderedMap<&'static str, Function> = phf_ordered_map!
     "mplay_data_write" => Function::Engine(Game::mplay_data_write),
     "mplay_data_read" => Function::Engine(Game::mplay_data_read),
     "mplay_data_mode" => Function::Engine(Game::mplay_data_mode),
-    "mplay_message_send" => Function::Volatile(Game::mplay_message_send),
-    "mplay_message_send_guaranteed" => Function::Volatile(Game::mplay_message_send_guaranteed),
+    "mplay_message_send" => Function::Engine(Game::mplay_message_send),
+    "mplay_message_send_guaranteed" => Function::Engine(Game::mplay_message_send_guaranteed),
     "mplay_message_receive" => Function::Engine(Game::mplay_message_receive),
     "mplay_message_id" => Function::Constant(Game::mplay_message_id),
     "mplay_message_value" => Function::Constant(Game::mplay_message_value),
     "mplay_message_player" => Function::Constant(Game::mplay_message_player),
     "mplay_message_name" => Function::Constant(Game::mplay_message_name),
-    "mplay_message_count" => Function::Constant(Game::mplay_message_count),
+    "mplay_message_count" => Function::Engine(Game::mplay_message_count),
     "mplay_message_clear" => Function::Engine(Game::mplay_message_clear),
     "mplay_ipaddress" => Function::Engine(Game::mplay_ipaddress),
     "event_inherited" => Function::Runtime(Game::event_inherited),
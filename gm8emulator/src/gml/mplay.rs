@@ -0,0 +1,654 @@
+//! A stand-in for GM8's DirectPlay-based multiplayer functions, using a small protocol over TCP.
+//! It only talks to other copies of the emulator, not to games running in real GM8.
+
+use crate::gml::Value;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Port used when the address given to mplay_init_tcpip doesn't name one. Same as DirectPlay's.
+pub const DEFAULT_PORT: u16 = 47624;
+
+/// Number of shared data slots, as in GM8.
+pub const DATA_SLOTS: usize = 10000;
+
+/// How long finding or joining a session may take in all, since the game waits on it as in GM8.
+/// A peer which stops taking our data for this long is dropped.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Player id given to whoever created the session.
+const HOST_ID: i32 = 1;
+
+/// Longest packet we'll send or accept. A peer announcing anything longer gets dropped.
+const MAX_PACKET_LEN: usize = 8 * 1024 * 1024;
+
+/// Most unsent data to hold for a peer before dropping it.
+const MAX_QUEUED_LEN: usize = 4 * MAX_PACKET_LEN;
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Player {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Clone)]
+pub struct Message {
+    pub player: i32,
+    pub id: i32,
+    pub value: Value,
+}
+
+#[derive(Deserialize, Serialize)]
+enum Packet {
+    // Handshake
+    Query,
+    SessionInfo { name: String },
+    Join { name: String },
+    Refused,
+    Welcome { id: i32, players: Vec<Player>, data: Vec<(usize, Value)> },
+
+    // Session
+    PlayerJoined(Player),
+    PlayerLeft(i32),
+    Message { from: i32, to: i32, id: i32, value: Value },
+    DataWrite { index: usize, value: Value },
+}
+
+/// A TCP stream carrying length-prefixed packets. Neither reads nor writes ever block.
+struct Connection {
+    stream: TcpStream,
+    incoming: Vec<u8>,
+    outgoing: Vec<u8>,
+    packets: VecDeque<Packet>,
+    opened: Instant,
+    stalled_since: Option<Instant>, // when the peer last stopped taking our data, if it has
+    broken: bool,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        stream.set_nonblocking(true)?;
+        Ok(Self {
+            stream,
+            incoming: Vec::new(),
+            outgoing: Vec::new(),
+            packets: VecDeque::new(),
+            opened: Instant::now(),
+            stalled_since: None,
+            broken: false,
+        })
+    }
+
+    fn connect(address: SocketAddr) -> io::Result<Self> {
+        let start = Instant::now();
+        let mut conn = Self::new(TcpStream::connect_timeout(&address, TIMEOUT)?)?;
+        // Connecting counts towards the time given to wait()
+        conn.opened = start;
+        Ok(conn)
+    }
+
+    /// Queues a packet and sends as much as can go out right away. An error means the connection is gone,
+    /// except for a packet that's too long, which is just not sent.
+    fn send(&mut self, packet: &Packet) -> io::Result<()> {
+        let data = bincode::serialize(packet).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if data.len() > MAX_PACKET_LEN {
+            return Err(io::ErrorKind::InvalidData.into())
+        }
+        if !self.broken {
+            self.outgoing.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            self.outgoing.extend_from_slice(&data);
+        }
+        self.flush()
+    }
+
+    /// Sends whatever queued data the stream will take without blocking.
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.write_queued();
+        if result.is_err() {
+            self.broken = true;
+        }
+        result
+    }
+
+    fn write_queued(&mut self) -> io::Result<()> {
+        if self.broken {
+            return Err(io::ErrorKind::BrokenPipe.into())
+        }
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => {
+                    self.outgoing.drain(..n);
+                    self.stalled_since = None;
+                },
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    let stalled_since = *self.stalled_since.get_or_insert_with(Instant::now);
+                    if stalled_since.elapsed() > TIMEOUT || self.outgoing.len() > MAX_QUEUED_LEN {
+                        return Err(io::ErrorKind::TimedOut.into())
+                    }
+                    break
+                },
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets the next packet which has arrived, if any. An error means the connection is gone.
+    fn receive(&mut self) -> io::Result<Option<Packet>> {
+        self.flush()?;
+        if self.packets.is_empty() {
+            let mut buf = [0u8; 4096];
+            // Stop reading once there's enough for the longest packet, the rest can wait for the next call
+            while self.incoming.len() < 4 + MAX_PACKET_LEN {
+                match self.stream.read(&mut buf) {
+                    Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                    Ok(n) => self.incoming.extend_from_slice(&buf[..n]),
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                    Err(e) => return Err(e),
+                }
+            }
+            while self.incoming.len() >= 4 {
+                let len = u32::from_le_bytes([self.incoming[0], self.incoming[1], self.incoming[2], self.incoming[3]]);
+                if len as usize > MAX_PACKET_LEN {
+                    return Err(io::ErrorKind::InvalidData.into())
+                }
+                let end = 4 + len as usize;
+                if self.incoming.len() < end {
+                    break
+                }
+                let packet = bincode::deserialize(&self.incoming[4..end])
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                self.packets.push_back(packet);
+                self.incoming.drain(..end);
+            }
+        }
+        Ok(self.packets.pop_front())
+    }
+
+    /// Waits for the next packet, giving up once TIMEOUT has passed since the connection was opened.
+    fn wait(&mut self) -> io::Result<Packet> {
+        loop {
+            if let Some(packet) = self.receive()? {
+                return Ok(packet)
+            }
+            if self.opened.elapsed() > TIMEOUT {
+                return Err(io::ErrorKind::TimedOut.into())
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+enum Session {
+    None,
+    Hosting {
+        listener: TcpListener,
+        name: String,
+        max_players: usize, // 0 for no limit
+        pending: Vec<Connection>,
+        clients: Vec<(i32, Connection)>,
+        next_id: i32,
+    },
+    Joined(Connection),
+    Ended,
+}
+
+pub struct Multiplayer {
+    address: Option<SocketAddr>,
+    found: Vec<(SocketAddr, String)>,
+    session: Session,
+    local_id: i32,
+    players: Vec<Player>,
+    data: HashMap<usize, Value>,
+    messages: VecDeque<Message>,
+    last_message: Option<(Message, String)>,
+}
+
+impl Default for Multiplayer {
+    fn default() -> Self {
+        Self {
+            address: None,
+            found: Vec::new(),
+            session: Session::None,
+            local_id: 0,
+            players: Vec::new(),
+            data: HashMap::new(),
+            messages: VecDeque::new(),
+            last_message: None,
+        }
+    }
+}
+
+impl Multiplayer {
+    /// Sets the address of the host to look for sessions on. Returns whether it could be resolved.
+    pub fn init_tcpip(&mut self, address: &str) -> bool {
+        // GM8 searches the LAN when no address is given, but we only look on this machine.
+        let resolved = if address.is_empty() {
+            Some(SocketAddr::from((Ipv4Addr::LOCALHOST, DEFAULT_PORT)))
+        } else if address.contains(':') {
+            address.to_socket_addrs().ok().and_then(|mut x| x.next())
+        } else {
+            (address, DEFAULT_PORT).to_socket_addrs().ok().and_then(|mut x| x.next())
+        };
+        self.end();
+        self.address = resolved;
+        resolved.is_some()
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.address.is_some()
+    }
+
+    /// Leaves any session and forgets the connection settings.
+    pub fn end(&mut self) {
+        self.end_session();
+        self.address = None;
+        self.found.clear();
+    }
+
+    /// Looks for a session on the host address, returning how many were found.
+    pub fn find_sessions(&mut self) -> usize {
+        self.found.clear();
+        if let Some(address) = self.address {
+            let info = Connection::connect(address).and_then(|mut host| {
+                host.send(&Packet::Query)?;
+                host.wait()
+            });
+            if let Ok(Packet::SessionInfo { name }) = info {
+                self.found.push((address, name));
+            }
+        }
+        self.found.len()
+    }
+
+    /// Gets the name of a session found by the last find_sessions.
+    pub fn session_name(&self, index: usize) -> Option<&str> {
+        self.found.get(index).map(|(_, name)| name.as_str())
+    }
+
+    /// Hosts a session on every interface, on the port given to mplay_init_tcpip.
+    pub fn create_session(&mut self, name: &str, max_players: usize, player_name: &str) -> io::Result<()> {
+        self.end_session();
+        let port = self.address.map_or(DEFAULT_PORT, |a| a.port());
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, port))?;
+        listener.set_nonblocking(true)?;
+        self.session = Session::Hosting {
+            listener,
+            name: name.into(),
+            max_players,
+            pending: Vec::new(),
+            clients: Vec::new(),
+            next_id: HOST_ID + 1,
+        };
+        self.local_id = HOST_ID;
+        self.players.push(Player { id: HOST_ID, name: player_name.into() });
+        Ok(())
+    }
+
+    pub fn join_session(&mut self, index: usize, player_name: &str) -> io::Result<()> {
+        self.end_session();
+        let address = self.found.get(index).map(|(a, _)| *a).ok_or(io::ErrorKind::NotFound)?;
+        let mut host = Connection::connect(address)?;
+        host.send(&Packet::Join { name: player_name.into() })?;
+        match host.wait()? {
+            Packet::Welcome { id, players, data } => {
+                self.session = Session::Joined(host);
+                self.local_id = id;
+                self.players = players;
+                self.data = data.into_iter().collect();
+                Ok(())
+            },
+            Packet::Refused => Err(io::ErrorKind::ConnectionRefused.into()),
+            _ => Err(io::ErrorKind::InvalidData.into()),
+        }
+    }
+
+    /// Leaves the current session, closing it down if we're the host.
+    pub fn end_session(&mut self) {
+        // Dropping the connections is enough for everyone else to notice
+        self.session = Session::None;
+        self.local_id = 0;
+        self.players.clear();
+        self.data.clear();
+        self.messages.clear();
+        self.last_message = None;
+    }
+
+    /// Gets the session status the way mplay_session_status reports it.
+    pub fn session_status(&mut self) -> i32 {
+        self.poll();
+        match self.session {
+            Session::None => 0,
+            Session::Hosting { .. } => 1,
+            Session::Joined(_) => 2,
+            Session::Ended => 3,
+        }
+    }
+
+    pub fn players(&self) -> &[Player] {
+        &self.players
+    }
+
+    /// Resolves a player given by id or by name. Id 0 stands for all players.
+    pub fn resolve_player(&self, player: &Value) -> Option<i32> {
+        match player {
+            Value::Real(id) => Some(id.round().to_i32()),
+            Value::Str(name) => {
+                let name = String::from_utf8_lossy(name.as_ref());
+                self.players.iter().find(|p| p.name == name).map(|p| p.id)
+            },
+        }
+    }
+
+    /// Sends a message to a player, or everyone else if `to` is 0. Returns whether it went anywhere.
+    pub fn send_message(&mut self, to: i32, id: i32, value: Value) -> bool {
+        let packet = Packet::Message { from: self.local_id, to, id, value };
+        match &mut self.session {
+            Session::Hosting { clients, .. } => clients
+                .iter_mut()
+                .filter(|(client, _)| to == 0 || to == *client)
+                .fold(false, |sent, (_, conn)| conn.send(&packet).is_ok() || sent),
+            Session::Joined(host) => to != self.local_id && host.send(&packet).is_ok(),
+            _ => false,
+        }
+    }
+
+    /// Takes the oldest message from the given player, or anyone if `from` is 0.
+    pub fn receive_message(&mut self, from: i32) -> bool {
+        self.poll();
+        match self.messages.iter().position(|m| from == 0 || m.player == from) {
+            Some(index) => {
+                let message = self.messages.remove(index).unwrap();
+                let name = self.players.iter().find(|p| p.id == message.player).map(|p| p.name.clone());
+                self.last_message = Some((message, name.unwrap_or_default()));
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Gets the last message taken by receive_message, along with its sender's name.
+    pub fn last_message(&self) -> Option<&(Message, String)> {
+        self.last_message.as_ref()
+    }
+
+    pub fn message_count(&mut self, from: i32) -> usize {
+        self.poll();
+        self.messages.iter().filter(|m| from == 0 || m.player == from).count()
+    }
+
+    pub fn clear_messages(&mut self, from: i32) {
+        self.poll();
+        self.messages.retain(|m| from != 0 && m.player != from);
+    }
+
+    pub fn data_read(&mut self, index: usize) -> Value {
+        self.poll();
+        self.data.get(&index).cloned().unwrap_or_default()
+    }
+
+    pub fn data_write(&mut self, index: usize, value: Value) {
+        if index >= DATA_SLOTS {
+            return
+        }
+        let packet = Packet::DataWrite { index, value: value.clone() };
+        match &mut self.session {
+            Session::Hosting { clients, .. } => {
+                for (_, conn) in clients.iter_mut() {
+                    let _ = conn.send(&packet);
+                }
+            },
+            Session::Joined(host) => {
+                let _ = host.send(&packet);
+            },
+            _ => (),
+        }
+        self.data.insert(index, value);
+    }
+
+    /// Handles everything that's arrived over the network since the last poll.
+    pub fn poll(&mut self) {
+        let mut ended = false;
+        match &mut self.session {
+            Session::Hosting { listener, name, max_players, pending, clients, next_id } => {
+                while let Ok((stream, _)) = listener.accept() {
+                    if let Ok(conn) = Connection::new(stream) {
+                        pending.push(conn);
+                    }
+                }
+
+                // New connections either ask about the session or try to join it. Answered ones are kept
+                // until they hang up, so the answer isn't cut off, but nobody gets to hang around for long.
+                pending.retain(|conn| conn.opened.elapsed() <= TIMEOUT);
+                let mut i = 0;
+                while i < pending.len() {
+                    match pending[i].receive() {
+                        Ok(None) => i += 1,
+                        Ok(Some(Packet::Query)) => {
+                            let _ = pending[i].send(&Packet::SessionInfo { name: name.clone() });
+                        },
+                        Ok(Some(Packet::Join { name: player_name })) => {
+                            if *max_players != 0 && self.players.len() >= *max_players {
+                                let _ = pending[i].send(&Packet::Refused);
+                                continue
+                            }
+                            let mut conn = pending.remove(i);
+                            let player = Player { id: *next_id, name: player_name };
+                            let mut players = self.players.clone();
+                            players.push(player.clone());
+                            let data = self.data.iter().map(|(k, v)| (*k, v.clone())).collect();
+                            if conn.send(&Packet::Welcome { id: player.id, players, data }).is_ok() {
+                                for (_, client) in clients.iter_mut() {
+                                    let _ = client.send(&Packet::PlayerJoined(player.clone()));
+                                }
+                                clients.push((player.id, conn));
+                                self.players.push(player);
+                                *next_id += 1;
+                            }
+                        },
+                        Ok(Some(_)) | Err(_) => {
+                            pending.remove(i);
+                        },
+                    }
+                }
+
+                // Everything from players in the session goes through us, so pass it on as needed
+                let mut left = Vec::new();
+                for i in 0..clients.len() {
+                    let from = clients[i].0;
+                    loop {
+                        let packet = match clients[i].1.receive() {
+                            Ok(Some(packet)) => packet,
+                            Ok(None) => break,
+                            Err(_) => {
+                                left.push(from);
+                                break
+                            },
+                        };
+                        match packet {
+                            Packet::Message { to, id, value, .. } => {
+                                let relay = Packet::Message { from, to, id, value: value.clone() };
+                                for (client, conn) in clients.iter_mut() {
+                                    if *client != from && (to == 0 || to == *client) {
+                                        let _ = conn.send(&relay);
+                                    }
+                                }
+                                if to == 0 || to == self.local_id {
+                                    self.messages.push_back(Message { player: from, id, value });
+                                }
+                            },
+                            Packet::DataWrite { index, value } if index < DATA_SLOTS => {
+                                let relay = Packet::DataWrite { index, value: value.clone() };
+                                for (client, conn) in clients.iter_mut() {
+                                    if *client != from {
+                                        let _ = conn.send(&relay);
+                                    }
+                                }
+                                self.data.insert(index, value);
+                            },
+                            _ => (),
+                        }
+                    }
+                }
+                for id in left {
+                    clients.retain(|(client, _)| *client != id);
+                    self.players.retain(|p| p.id != id);
+                    for (_, conn) in clients.iter_mut() {
+                        let _ = conn.send(&Packet::PlayerLeft(id));
+                    }
+                }
+            },
+            Session::Joined(host) => loop {
+                match host.receive() {
+                    Ok(Some(Packet::Message { from, id, value, .. })) => {
+                        self.messages.push_back(Message { player: from, id, value })
+                    },
+                    Ok(Some(Packet::DataWrite { index, value })) => {
+                        self.data.insert(index, value);
+                    },
+                    Ok(Some(Packet::PlayerJoined(player))) => self.players.push(player),
+                    Ok(Some(Packet::PlayerLeft(id))) => self.players.retain(|p| p.id != id),
+                    Ok(Some(_)) => (),
+                    Ok(None) => break,
+                    Err(_) => {
+                        ended = true;
+                        break
+                    },
+                }
+            },
+            Session::None | Session::Ended => (),
+        }
+        if ended {
+            // The host is gone, which ends the session for everyone
+            self.session = Session::Ended;
+            self.players.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn connection_pair() -> (Connection, Connection) {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let a = Connection::connect(listener.local_addr().unwrap()).unwrap();
+        let (b, _) = listener.accept().unwrap();
+        (a, Connection::new(b).unwrap())
+    }
+
+    #[test]
+    fn framing() {
+        let (mut a, mut b) = connection_pair();
+        a.send(&Packet::Join { name: "abc".into() }).unwrap();
+        a.send(&Packet::PlayerLeft(5)).unwrap();
+        assert!(matches!(b.wait(), Ok(Packet::Join { name }) if name == "abc"));
+        assert!(matches!(b.wait(), Ok(Packet::PlayerLeft(5))));
+    }
+
+    #[test]
+    fn partial_packet() {
+        let (a, mut b) = connection_pair();
+        let data = bincode::serialize(&Packet::PlayerLeft(5)).unwrap();
+        let mut stream = &a.stream;
+        stream.write_all(&(data.len() as u32).to_le_bytes()).unwrap();
+        stream.write_all(&data[..1]).unwrap();
+        thread::sleep(Duration::from_millis(50));
+        assert!(matches!(b.receive(), Ok(None)));
+        stream.write_all(&data[1..]).unwrap();
+        assert!(matches!(b.wait(), Ok(Packet::PlayerLeft(5))));
+    }
+
+    #[test]
+    fn oversized_packet() {
+        let (mut a, mut b) = connection_pair();
+        let too_long = Packet::SessionInfo { name: "x".repeat(MAX_PACKET_LEN) };
+        assert_eq!(a.send(&too_long).err().map(|e| e.kind()), Some(io::ErrorKind::InvalidData));
+
+        (&a.stream).write_all(&(MAX_PACKET_LEN as u32 + 1).to_le_bytes()).unwrap();
+        assert_eq!(b.wait().err().map(|e| e.kind()), Some(io::ErrorKind::InvalidData));
+    }
+
+    #[test]
+    fn stalled_peer() {
+        let (mut a, _b) = connection_pair();
+        let packet = Packet::SessionInfo { name: "x".repeat(MAX_PACKET_LEN - 16) };
+        assert!((0..16).any(|_| a.send(&packet).is_err()));
+        assert!(a.receive().is_err());
+    }
+
+    #[test]
+    fn relay() {
+        let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap().local_addr().unwrap().port();
+        let address = format!("127.0.0.1:{}", port);
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let (stop_tx, stop_rx) = mpsc::channel();
+        let host_address = address.clone();
+        let host = thread::spawn(move || {
+            let mut host = Multiplayer::default();
+            assert!(host.init_tcpip(&host_address));
+            host.create_session("test", 0, "host").unwrap();
+            ready_tx.send(()).unwrap();
+            let mut received = Vec::new();
+            while stop_rx.try_recv().is_err() {
+                while host.receive_message(0) {
+                    let (message, name) = host.last_message().unwrap();
+                    received.push((message.player, message.id, name.clone()));
+                }
+                thread::sleep(Duration::from_millis(1));
+            }
+            received
+        });
+        ready_rx.recv().unwrap();
+
+        let join = |name: &str| {
+            let mut client = Multiplayer::default();
+            assert!(client.init_tcpip(&address));
+            assert_eq!(client.find_sessions(), 1);
+            assert_eq!(client.session_name(0), Some("test"));
+            client.join_session(0, name).unwrap();
+            client
+        };
+        let mut alice = join("alice");
+        let mut bob = join("bob");
+        let start = Instant::now();
+        while alice.players().len() != 3 {
+            assert!(start.elapsed() < TIMEOUT);
+            thread::sleep(Duration::from_millis(1));
+            alice.poll();
+        }
+
+        assert!(alice.send_message(0, 7, Value::from(3.0)));
+        let start = Instant::now();
+        while !bob.receive_message(0) {
+            assert!(start.elapsed() < TIMEOUT);
+            thread::sleep(Duration::from_millis(1));
+        }
+        let (message, name) = bob.last_message().unwrap();
+        assert_eq!((message.player, message.id, name.as_str()), (2, 7, "alice"));
+        assert_eq!(f64::from(message.value.clone()), 3.0);
+        assert_eq!(alice.message_count(0), 0);
+
+        // The host tells everyone else when a player goes away
+        drop(bob);
+        let start = Instant::now();
+        while alice.players().len() != 2 {
+            assert!(start.elapsed() < TIMEOUT);
+            thread::sleep(Duration::from_millis(1));
+            alice.poll();
+        }
+
+        stop_tx.send(()).unwrap();
+        assert_eq!(host.join().unwrap(), vec![(2, 7, "alice".to_string())]);
+    }
+}
@@ -90,3 +90,46 @@ fn rand_int() -> i32 {
     let _ = getrandom::getrandom(&mut bytes);
     i32::from_le_bytes(bytes)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Random;
+
+    #[test]
+    fn seed_sequence() {
+        let mut rand = Random::with_seed(12345);
+        let expected = [1655067934, 1242767767, 342459380, -1917230139, -1573353766, 1467157059];
+        for &seed in expected.iter() {
+            rand.cycle();
+            assert_eq!(rand.seed(), seed);
+        }
+    }
+
+    #[test]
+    fn next_int() {
+        let mut rand = Random::with_seed(12345);
+        let expected = [38, 29, 8, 55, 64, 34];
+        for &n in expected.iter() {
+            assert_eq!(rand.next_int(100), n);
+        }
+    }
+
+    #[test]
+    fn next() {
+        let mut rand = Random::with_seed(0);
+        assert_eq!(rand.next(1.0), 1.0 / 4294967296.0);
+        assert_eq!(rand.seed(), 1);
+        assert_eq!(rand.next(4294967296.0), 134775814.0);
+    }
+
+    #[test]
+    fn set_seed() {
+        let mut a = Random::new();
+        let mut b = Random::with_seed(-7);
+        a.set_seed(-7);
+        for _ in 0..16 {
+            assert_eq!(a.next(1000.0), b.next(1000.0));
+        }
+        assert_eq!(a.seed(), b.seed());
+    }
+}
@@ -1137,8 +1137,11 @@ impl Game {
             InstanceVariable::MouseLastbutton => Ok(f64::from(self.input.mouse_lastbutton()).into()),
             InstanceVariable::KeyboardKey => Ok(f64::from(self.input.keyboard_key()).into()),
             InstanceVariable::KeyboardLastkey => Ok(f64::from(self.input.keyboard_lastkey()).into()),
-            InstanceVariable::KeyboardLastchar => todo!("keyboard_lastchar getter"),
-            InstanceVariable::KeyboardString => todo!("keyboard_string getter"),
+            InstanceVariable::KeyboardLastchar => match self.input.keyboard_lastchar() {
+                0 => Ok("".into()),
+                chr => Ok(vec![chr].into()),
+            },
+            InstanceVariable::KeyboardString => Ok(self.input.keyboard_string().into()),
             InstanceVariable::CursorSprite => Ok(self.cursor_sprite.into()),
             InstanceVariable::ShowScore => Ok(self.score_capt_d.into()),
             InstanceVariable::ShowLives => Ok(self.lives_capt_d.into()),
@@ -1497,8 +1500,11 @@ impl Game {
                     self.input.set_keyboard_lastkey(vk);
                 }
             },
-            InstanceVariable::KeyboardLastchar => todo!("keyboard_lastchar setter"),
-            InstanceVariable::KeyboardString => todo!("keyboard_string setter"),
+            InstanceVariable::KeyboardLastchar => {
+                let string: &[u8] = (&value).into();
+                self.input.set_keyboard_lastchar(string.first().copied().unwrap_or(0));
+            },
+            InstanceVariable::KeyboardString => self.input.set_keyboard_string((&value).into()),
             InstanceVariable::CursorSprite => self.cursor_sprite = value.round(),
             InstanceVariable::ShowScore => {
                 self.has_set_show_score = true;
@@ -104,3 +104,68 @@ impl<'de> Deserialize<'de> for String {
         deserializer.deserialize_bytes(SerdeVisitor)
     }
 }
+
+// GM8's 1-based indexing rules, shared by the byte (8.0) and character (8.1) versions of the string functions.
+
+/// Gets the element at a 1-based position, as string_char_at does. Positions below 1 count as 1.
+pub fn char_at<T>(s: &[T], pos: i32) -> Option<&T> {
+    s.get(pos.saturating_sub(1).max(0) as usize)
+}
+
+/// Gets `len` elements starting at a 1-based position, as string_copy does. Positions below 1 count as 1.
+pub fn copy<T>(s: &[T], start: i32, len: i32) -> &[T] {
+    let start = (start.saturating_sub(1).max(0) as usize).min(s.len());
+    let end = start.saturating_add(len.max(0) as usize).min(s.len());
+    &s[start..end]
+}
+
+/// Removes `len` elements starting at a 1-based position, as string_delete does.
+/// Unlike the others, a position below 1 leaves the string unchanged.
+pub fn delete<T: Clone>(s: &[T], start: i32, len: i32) -> Vec<T> {
+    if start < 1 || len < 1 {
+        return s.to_vec()
+    }
+    let start = (start as usize - 1).min(s.len());
+    let end = start.saturating_add(len as usize).min(s.len());
+    s[..start].iter().chain(&s[end..]).cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn char_at_bounds() {
+        let s = b"abc";
+        assert_eq!(char_at(s, 0), Some(&b'a'));
+        assert_eq!(char_at(s, 1), Some(&b'a'));
+        assert_eq!(char_at(s, 3), Some(&b'c'));
+        assert_eq!(char_at(s, 4), None);
+        assert_eq!(char_at(b"", 0), None);
+        assert_eq!(char_at(b"", 1), None);
+    }
+
+    #[test]
+    fn copy_bounds() {
+        let s = b"abc";
+        assert_eq!(copy(s, 0, 2), b"ab");
+        assert_eq!(copy(s, 1, 2), b"ab");
+        assert_eq!(copy(s, 3, 5), b"c");
+        assert_eq!(copy(s, 4, 1), b"");
+        assert_eq!(copy(s, 2, 0), b"");
+        assert_eq!(copy(s, 2, -1), b"");
+        assert_eq!(copy(b"", 1, 1), b"");
+    }
+
+    #[test]
+    fn delete_bounds() {
+        let s = b"abc";
+        assert_eq!(delete(s, 0, 1), b"abc");
+        assert_eq!(delete(s, 1, 1), b"bc");
+        assert_eq!(delete(s, 3, 1), b"ab");
+        assert_eq!(delete(s, 4, 1), b"abc");
+        assert_eq!(delete(s, 2, 10), b"a");
+        assert_eq!(delete(s, 1, 0), b"abc");
+        assert_eq!(delete(b"", 1, 1), b"");
+    }
+}
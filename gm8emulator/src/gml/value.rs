@@ -587,4 +587,50 @@ mod tests {
         let b = Value::Str("owo".to_string().into());
         let _ = a.add(b).unwrap();
     }
+
+    #[test]
+    fn cmp_epsilon() {
+        let a = Value::Real(Real::from(0.1) + Real::from(0.2));
+        let b = Value::Real(Real::from(0.3));
+        assert!(a.clone().gml_eq(b.clone()).unwrap().is_truthy());
+        assert!(!a.clone().gml_ne(b.clone()).unwrap().is_truthy());
+        assert!(!a.clone().gml_lt(b.clone()).unwrap().is_truthy());
+        assert!(a.clone().gml_lte(b.clone()).unwrap().is_truthy());
+        assert!(!a.clone().gml_gt(b.clone()).unwrap().is_truthy());
+        assert!(a.gml_gte(b).unwrap().is_truthy());
+
+        let c = Value::Real(Real::from(1.0));
+        let d = Value::Real(Real::from(1.0 + 1e-12));
+        assert!(!c.clone().gml_eq(d.clone()).unwrap().is_truthy());
+        assert!(c.gml_lt(d).unwrap().is_truthy());
+    }
+
+    #[test]
+    fn cmp_strings() {
+        let a = Value::Str("abc".into());
+        let b = Value::Str("abd".into());
+        assert!(a.clone().gml_lt(b.clone()).unwrap().is_truthy());
+        assert!(!a.clone().gml_eq(b.clone()).unwrap().is_truthy());
+        assert!(a.clone().gml_eq(a.clone()).unwrap().is_truthy());
+        assert!(b.gml_gte(a).unwrap().is_truthy());
+    }
+
+    #[test]
+    fn cmp_invalid() {
+        let a = Value::Real(Real::from(1.0));
+        let b = Value::Str("1".into());
+        assert!(a.clone().gml_eq(b.clone()).is_err());
+        assert!(b.clone().gml_lt(a.clone()).is_err());
+        assert!(a.clone().sub(b.clone()).is_err());
+        assert!(b.div(a).is_err());
+    }
+
+    #[test]
+    fn repr() {
+        assert_eq!(Value::Real(Real::from(3.14159)).repr().as_ref(), b"3.14");
+        assert_eq!(Value::Real(Real::from(2.0)).repr().as_ref(), b"2");
+        assert_eq!(Value::Real(Real::from(-0.5)).repr().as_ref(), b"-0.50");
+        assert_eq!(Value::Real(Real::from(1.005)).repr().as_ref(), b"1.00");
+        assert_eq!(Value::Str("".into()).repr().as_ref(), b"");
+    }
 }
@@ -2,11 +2,15 @@ use crate::types::ArraySerde;
 use serde::{Deserialize, Serialize};
 use std::{convert::TryFrom, num::NonZeroI32};
 
+#[cfg(target_os = "windows")]
+mod xinput;
+
 const KEY_MAX: usize = u8::max_value() as usize + 1;
 const MB_ANY: i8 = -1;
 const MB_NONE: i8 = 0;
 const VK_NOKEY: u8 = 0; // TODO: dont redefine
 const VK_ANYKEY: u8 = 1; // TODO: dont redefine
+const JOYSTICK_COUNT: usize = 2;
 
 #[derive(Copy, Clone, Debug, Deserialize, Serialize)]
 #[repr(u8)]
@@ -458,6 +462,44 @@ const fn make_vk_fn_input_remap() -> [u8; KEY_MAX] {
 }
 const VK_FN_INPUT_REMAP: [u8; KEY_MAX] = make_vk_fn_input_remap();
 
+// GM8 caps keyboard_string at this many characters, discarding from the front
+const KEYBOARD_STRING_MAX: usize = 1024;
+
+/// Translates a keypress into the character it would type on a US layout, if any.
+/// This is done here rather than using the OS' character events so that replays stay deterministic.
+/// Caps Lock inverts the case of letters, and the keypad only types digits while Num Lock is on.
+fn vk2char(vk: u8, shift: bool, capslock: bool, numlock: bool) -> Option<u8> {
+    const SHIFTED_DIGITS: &[u8; 10] = b")!@#$%^&*(";
+    let pick = |normal: u8, shifted: u8| Some(if shift { shifted } else { normal });
+    match vk {
+        x if x == Button::Backspace as u8 => Some(0x08),
+        x if x == Button::Tab as u8 => Some(0x09),
+        x if x == Button::Return as u8 => Some(0x0D),
+        x if x == Button::Escape as u8 => Some(0x1B),
+        x if x == Button::Space as u8 => Some(b' '),
+        x @ 0x30..=0x39 => pick(x, SHIFTED_DIGITS[usize::from(x - 0x30)]),
+        x @ 0x41..=0x5A => Some(if shift != capslock { x } else { x + (b'a' - b'A') }),
+        x @ 0x60..=0x69 if numlock => Some(x - 0x60 + b'0'),
+        x if x == Button::KeypadMultiply as u8 => Some(b'*'),
+        x if x == Button::KeypadAdd as u8 => Some(b'+'),
+        x if x == Button::KeypadSubtract as u8 => Some(b'-'),
+        x if x == Button::KeypadDecimal as u8 && numlock => Some(b'.'),
+        x if x == Button::KeypadDivide as u8 => Some(b'/'),
+        x if x == Button::Oem1 as u8 => pick(b';', b':'),
+        x if x == Button::OemPlus as u8 => pick(b'=', b'+'),
+        x if x == Button::OemComma as u8 => pick(b',', b'<'),
+        x if x == Button::OemMinus as u8 => pick(b'-', b'_'),
+        x if x == Button::OemPeriod as u8 => pick(b'.', b'>'),
+        x if x == Button::Oem2 as u8 => pick(b'/', b'?'),
+        x if x == Button::Oem3 as u8 => pick(b'`', b'~'),
+        x if x == Button::Oem4 as u8 => pick(b'[', b'{'),
+        x if x == Button::Oem5 as u8 => pick(b'\\', b'|'),
+        x if x == Button::Oem6 as u8 => pick(b']', b'}'),
+        x if x == Button::Oem7 as u8 => pick(b'\'', b'"'),
+        _ => None,
+    }
+}
+
 #[derive(Copy, Clone, Debug, Deserialize, Serialize)]
 #[repr(i8)]
 pub enum MouseButton {
@@ -511,6 +553,42 @@ const fn gen_default_keymap() -> [u8; KEY_MAX] {
 }
 const DEFAULT_KEYMAP: [u8; KEY_MAX] = gen_default_keymap();
 
+/// Snapshot of a connected joystick, laid out the way GM8's joystick functions see it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Joystick {
+    pub name: String,
+    pub axes: [f64; 6], // x, y, z, r, u, v, each in -1..1
+    pub axis_count: u8,
+    pub buttons: u32, // bit n is button n+1
+    pub button_count: u8,
+    pub has_pov: bool,
+    pub pov: i32, // degrees clockwise from up, or -1 if centered
+}
+
+impl Joystick {
+    /// Gets the value of an axis, where 0 is x and 5 is v.
+    pub fn axis(&self, axis: usize) -> f64 {
+        if axis < usize::from(self.axis_count) { self.axes[axis] } else { 0.0 }
+    }
+
+    /// Checks a 1-based button number.
+    pub fn button(&self, button: i32) -> bool {
+        (1..=i32::from(self.button_count)).contains(&button) && self.buttons & (1 << (button - 1)) != 0
+    }
+
+    /// Gets the direction of the stick as a numpad key code, vk_numpad5 meaning centered.
+    pub fn direction(&self) -> u8 {
+        // an axis counts as pushed once it's past half way
+        let step = |value: f64| match value {
+            v if v < -0.5 => -1,
+            v if v > 0.5 => 1,
+            _ => 0,
+        };
+        let (column, row) = (1 + step(self.axis(0)), 1 - step(self.axis(1)));
+        (Button::Keypad1 as i32 + row * 3 + column) as u8
+    }
+}
+
 #[derive(Clone, Deserialize, Serialize)]
 pub struct Input {
     // basic state
@@ -519,6 +597,7 @@ pub struct Input {
     button_state_press: ArraySerde<bool, KEY_MAX>,
     button_state_release: ArraySerde<bool, KEY_MAX>,
     mouse_position: (i32, i32),
+    mouse_window_position: (i32, i32),
     mouse_wheel: (bool, bool),
 
     // gamemaker weirdness
@@ -527,7 +606,18 @@ pub struct Input {
     mouse_current: i8,
     mouse_previous: i8,
     mouse_position_previous: (i32, i32),
-    numlock_state: bool, // spoofed!
+    numlock_state: bool,  // spoofed!
+    capslock_state: bool, // spoofed!
+
+    // text input
+    key_lastchar: u8,
+    key_string: Vec<u8>,
+
+    // keys pressed (true) or released (false) by keyboard_key_press/release, applied on the next poll
+    queued_keys: Vec<(u8, bool)>,
+
+    // joysticks 1 and 2, only polled during live play as they aren't recorded
+    joysticks: [Option<Joystick>; JOYSTICK_COUNT],
 }
 
 impl Input {
@@ -538,6 +628,7 @@ impl Input {
             button_state_press: ArraySerde([false; KEY_MAX]),
             button_state_release: ArraySerde([false; KEY_MAX]),
             mouse_position: (0, 0),
+            mouse_window_position: (0, 0),
             mouse_wheel: (false, false),
             key_current: 0,
             key_previous: 0,
@@ -545,16 +636,49 @@ impl Input {
             mouse_previous: 0,
             mouse_position_previous: (0, 0),
             numlock_state: false,
+            capslock_state: false,
+            key_lastchar: 0,
+            key_string: Vec::new(),
+            queued_keys: Vec::new(),
+            joysticks: [None, None],
         }
     }
 
     pub fn button_press(&mut self, code: u8, store_cur_prev: bool) {
         let code = VK_FN_INPUT_REMAP[code as usize];
+        // lock keys toggle when pressed, not when held
+        if !self.button_state[code as usize] {
+            if code == Button::NumLock as u8 {
+                self.numlock_state = !self.numlock_state;
+            } else if code == Button::CapsLock as u8 {
+                self.capslock_state = !self.capslock_state;
+            }
+        }
         self.button_state[code as usize] = true;
         self.button_state_press[code as usize] = true;
         if store_cur_prev {
             self.key_current = code;
             self.key_previous = code;
+            let shift = self.keyboard_check_internal(&self.button_state, Button::Shift as u8);
+            if let Some(chr) = vk2char(code, shift, self.capslock_state, self.numlock_state) {
+                self.type_char(chr);
+            }
+        }
+    }
+
+    fn type_char(&mut self, chr: u8) {
+        self.key_lastchar = chr;
+        match chr {
+            0x08 => {
+                self.key_string.pop();
+            },
+            0x20..=0x7E => {
+                self.key_string.push(chr);
+                if self.key_string.len() > KEYBOARD_STRING_MAX {
+                    self.key_string.remove(0);
+                }
+            },
+            _ => (),
         }
     }
 
@@ -567,6 +691,31 @@ impl Input {
         }
     }
 
+    /// Queues a simulated key press or release, which takes effect on the next call to `apply_queued_keys`.
+    pub fn queue_key(&mut self, code: u8, pressed: bool) {
+        self.queued_keys.push((code, pressed));
+    }
+
+    /// Applies any key presses and releases queued by `queue_key`, in the order they were queued.
+    pub fn apply_queued_keys(&mut self) {
+        for (code, pressed) in std::mem::take(&mut self.queued_keys) {
+            if pressed {
+                self.button_press(code, true);
+            } else {
+                self.button_release(code, true);
+            }
+        }
+    }
+
+    /// Releases every button which is currently held, such as when the window loses focus.
+    pub fn button_release_all(&mut self) {
+        for code in 0..KEY_MAX {
+            if self.button_state[code] {
+                self.button_release(code as u8, true);
+            }
+        }
+    }
+
     pub fn mouse_press(&mut self, code: i8, store_cur_prev: bool) {
         let button = match mouse2button(code) {
             Some(button) => button,
@@ -582,6 +731,14 @@ impl Input {
     #[inline]
     pub fn mouse_move_to(&mut self, pos: (i32, i32)) {
         self.mouse_position = pos;
+        self.mouse_window_position = pos;
+    }
+
+    /// Moves the mouse when the window doesn't line up with the framebuffer, such as when it's letterboxed.
+    #[inline]
+    pub fn mouse_move_to_scaled(&mut self, pos: (i32, i32), window_pos: (i32, i32)) {
+        self.mouse_position = pos;
+        self.mouse_window_position = window_pos;
     }
 
     pub fn mouse_release(&mut self, code: i8, store_cur_prev: bool) {
@@ -703,7 +860,7 @@ impl Input {
     pub fn keyboard_clear_all(&mut self) {
         self.key_current = 0;
         self.key_previous = 0;
-        // TODO: self.key_lastchar = 0;
+        self.key_lastchar = 0;
         self.button_state.iter_mut().for_each(|x| *x = false);
         self.button_state_press.iter_mut().for_each(|x| *x = false);
         self.button_state_release.iter_mut().for_each(|x| *x = false);
@@ -753,6 +910,27 @@ impl Input {
         self.key_previous = vk;
     }
 
+    #[inline]
+    pub fn keyboard_lastchar(&self) -> u8 {
+        self.key_lastchar
+    }
+
+    #[inline]
+    pub fn set_keyboard_lastchar(&mut self, chr: u8) {
+        self.key_lastchar = chr;
+    }
+
+    #[inline]
+    pub fn keyboard_string(&self) -> &[u8] {
+        &self.key_string
+    }
+
+    pub fn set_keyboard_string(&mut self, string: &[u8]) {
+        let start = string.len().saturating_sub(KEYBOARD_STRING_MAX);
+        self.key_string.clear();
+        self.key_string.extend_from_slice(&string[start..]);
+    }
+
     fn mouse_check_button_internal_indirect(&self, state: &[bool; KEY_MAX], mb: i8) -> bool {
         match mb {
             MB_ANY => {
@@ -863,6 +1041,16 @@ impl Input {
         self.mouse_position.1
     }
 
+    #[inline]
+    pub fn mouse_window_x(&self) -> i32 {
+        self.mouse_window_position.0
+    }
+
+    #[inline]
+    pub fn mouse_window_y(&self) -> i32 {
+        self.mouse_window_position.1
+    }
+
     #[inline]
     pub fn mouse_x_previous(&self) -> i32 {
         self.mouse_position_previous.0
@@ -873,6 +1061,20 @@ impl Input {
         self.mouse_position_previous.1
     }
 
+    /// Gets the joystick with the given 1-based id, if it's connected.
+    pub fn joystick(&self, id: i32) -> Option<&Joystick> {
+        usize::try_from(id).ok().and_then(|id| id.checked_sub(1)).and_then(|i| self.joysticks.get(i)?.as_ref())
+    }
+
+    /// Refreshes the joystick state from the OS.
+    /// Does nothing on platforms without a joystick backend.
+    pub fn poll_joysticks(&mut self) {
+        #[cfg(target_os = "windows")]
+        for (i, joystick) in self.joysticks.iter_mut().enumerate() {
+            *joystick = xinput::poll(i as u32);
+        }
+    }
+
     /// Clears the button press and release buffers.
     /// Should be called after each frame.
     pub fn step(&mut self) {
@@ -890,3 +1092,25 @@ impl Input {
         *self = Self::new();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vk2char_locks() {
+        let a = Button::A as u8;
+        assert_eq!(vk2char(a, false, false, false), Some(b'a'));
+        assert_eq!(vk2char(a, true, false, false), Some(b'A'));
+        assert_eq!(vk2char(a, false, true, false), Some(b'A'));
+        assert_eq!(vk2char(a, true, true, false), Some(b'a'));
+
+        // caps lock only affects letters
+        assert_eq!(vk2char(Button::Alpha1 as u8, false, true, false), Some(b'1'));
+
+        assert_eq!(vk2char(Button::Keypad1 as u8, false, false, true), Some(b'1'));
+        assert_eq!(vk2char(Button::Keypad1 as u8, false, false, false), None);
+        assert_eq!(vk2char(Button::KeypadDecimal as u8, false, false, false), None);
+        assert_eq!(vk2char(Button::KeypadAdd as u8, false, false, false), Some(b'+'));
+    }
+}
@@ -0,0 +1,120 @@
+#![allow(bad_style)]
+
+use super::Joystick;
+
+type BYTE = u8;
+type DWORD = u32;
+type SHORT = i16;
+type WORD = u16;
+
+const ERROR_SUCCESS: DWORD = 0;
+
+const XINPUT_GAMEPAD_DPAD_UP: WORD = 0x0001;
+const XINPUT_GAMEPAD_DPAD_DOWN: WORD = 0x0002;
+const XINPUT_GAMEPAD_DPAD_LEFT: WORD = 0x0004;
+const XINPUT_GAMEPAD_DPAD_RIGHT: WORD = 0x0008;
+const XINPUT_GAMEPAD_START: WORD = 0x0010;
+const XINPUT_GAMEPAD_BACK: WORD = 0x0020;
+const XINPUT_GAMEPAD_LEFT_THUMB: WORD = 0x0040;
+const XINPUT_GAMEPAD_RIGHT_THUMB: WORD = 0x0080;
+const XINPUT_GAMEPAD_LEFT_SHOULDER: WORD = 0x0100;
+const XINPUT_GAMEPAD_RIGHT_SHOULDER: WORD = 0x0200;
+const XINPUT_GAMEPAD_A: WORD = 0x1000;
+const XINPUT_GAMEPAD_B: WORD = 0x2000;
+const XINPUT_GAMEPAD_X: WORD = 0x4000;
+const XINPUT_GAMEPAD_Y: WORD = 0x8000;
+
+#[repr(C)]
+#[derive(Default)]
+struct XINPUT_GAMEPAD {
+    wButtons: WORD,
+    bLeftTrigger: BYTE,
+    bRightTrigger: BYTE,
+    sThumbLX: SHORT,
+    sThumbLY: SHORT,
+    sThumbRX: SHORT,
+    sThumbRY: SHORT,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct XINPUT_STATE {
+    dwPacketNumber: DWORD,
+    Gamepad: XINPUT_GAMEPAD,
+}
+
+// xinput9_1_0 ships with every Windows since Vista, unlike the newer xinput1_x versions.
+#[link(name = "xinput9_1_0")]
+extern "system" {
+    fn XInputGetState(dwUserIndex: DWORD, pState: *mut XINPUT_STATE) -> DWORD;
+}
+
+/// Button order as reported by the DirectInput driver for XInput pads, which is what GM8 sees.
+const BUTTON_ORDER: [WORD; 10] = [
+    XINPUT_GAMEPAD_A,
+    XINPUT_GAMEPAD_B,
+    XINPUT_GAMEPAD_X,
+    XINPUT_GAMEPAD_Y,
+    XINPUT_GAMEPAD_LEFT_SHOULDER,
+    XINPUT_GAMEPAD_RIGHT_SHOULDER,
+    XINPUT_GAMEPAD_BACK,
+    XINPUT_GAMEPAD_START,
+    XINPUT_GAMEPAD_LEFT_THUMB,
+    XINPUT_GAMEPAD_RIGHT_THUMB,
+];
+
+fn thumb(value: SHORT) -> f64 {
+    (f64::from(value) / f64::from(SHORT::MAX)).max(-1.0)
+}
+
+fn trigger(value: BYTE) -> f64 {
+    f64::from(value) / f64::from(BYTE::MAX)
+}
+
+/// Polls the XInput controller in the given slot (0-3), returning None if nothing is plugged in.
+pub fn poll(index: u32) -> Option<Joystick> {
+    let mut state = XINPUT_STATE::default();
+    if unsafe { XInputGetState(index, &mut state) } != ERROR_SUCCESS {
+        return None
+    }
+    let pad = &state.Gamepad;
+
+    let buttons = BUTTON_ORDER
+        .iter()
+        .enumerate()
+        .filter(|(_, &mask)| pad.wButtons & mask != 0)
+        .fold(0, |acc, (i, _)| acc | (1 << i));
+
+    let held = |mask: WORD| i32::from(pad.wButtons & mask != 0);
+    let dx = held(XINPUT_GAMEPAD_DPAD_RIGHT) - held(XINPUT_GAMEPAD_DPAD_LEFT);
+    let dy = held(XINPUT_GAMEPAD_DPAD_UP) - held(XINPUT_GAMEPAD_DPAD_DOWN);
+    let pov = match (dx, dy) {
+        (0, 1) => 0,
+        (1, 1) => 45,
+        (1, 0) => 90,
+        (1, -1) => 135,
+        (0, -1) => 180,
+        (-1, -1) => 225,
+        (-1, 0) => 270,
+        (-1, 1) => 315,
+        _ => -1,
+    };
+
+    Some(Joystick {
+        name: "Controller (XBOX 360 For Windows)".into(),
+        // Y and R point downwards, and both triggers share the Z axis
+        axes: [
+            thumb(pad.sThumbLX),
+            -thumb(pad.sThumbLY),
+            trigger(pad.bLeftTrigger) - trigger(pad.bRightTrigger),
+            -thumb(pad.sThumbRY),
+            thumb(pad.sThumbRX),
+            0.0,
+        ],
+        axis_count: 5,
+        buttons,
+        button_count: BUTTON_ORDER.len() as u8,
+        has_pov: true,
+        pov,
+    })
+}
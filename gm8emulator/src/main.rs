@@ -53,6 +53,9 @@ fn xmain() -> i32 {
     opts.optflag("v", "verbose", "enables verbose logging");
     opts.optflag("r", "realtime", "disables clock spoofing");
     opts.optflag("l", "no-framelimit", "disables the frame-limiter");
+    opts.optopt("c", "fps-cap", "limits frames to this rate if it's below room_speed", "FPS");
+    opts.optflag("H", "headless", "runs without a window or graphics context");
+    opts.optopt("F", "frames", "number of frames to run in headless mode", "N");
     opts.optopt("n", "project-name", "name of TAS project to create or load", "NAME");
     opts.optopt("f", "replay-file", "path to savestate file to replay", "FILE");
     opts.optopt("o", "output-file", "output savestate name in replay mode", "FILE.bin");
@@ -82,8 +85,23 @@ fn xmain() -> i32 {
     let multithread = !matches.opt_present("t");
     let spoof_time = !matches.opt_present("r");
     let frame_limiter = !matches.opt_present("l");
+    let fps_cap = match matches.opt_str("c").map(|n| n.parse::<u32>()).transpose() {
+        Ok(n) => n,
+        Err(e) => {
+            eprintln!("invalid fps cap for -c: {}", e);
+            return EXIT_FAILURE
+        },
+    };
     let verbose = matches.opt_present("v");
     let output_bin = matches.opt_str("o").map(PathBuf::from);
+    let headless = matches.opt_present("H");
+    let headless_frames = match matches.opt_str("F").map(|n| n.parse::<usize>()).transpose() {
+        Ok(n) => n,
+        Err(e) => {
+            eprintln!("invalid frame count for -F: {}", e);
+            return EXIT_FAILURE
+        },
+    };
     let project_path = matches.opt_str("n").map(|name| {
         let mut p = env::current_dir().expect("std::env::current_dir() failed");
         p.push("projects");
@@ -124,6 +142,11 @@ fn xmain() -> i32 {
                 path
             })
     });
+    if headless && project_path.is_some() {
+        eprintln!("headless mode can't be used with a TAS project");
+        return EXIT_FAILURE
+    }
+
     let can_clear_temp_dir = temp_dir.is_none();
     let replay = match matches
         .opt_str("f")
@@ -220,7 +243,7 @@ fn xmain() -> i32 {
     };
 
     let mut components =
-        match Game::launch(assets, absolute_path, game_args, temp_dir, encoding, frame_limiter, play_type) {
+        match Game::launch(assets, absolute_path, game_args, temp_dir, encoding, frame_limiter, play_type, headless) {
             Ok(g) => g,
             Err(e) => {
                 eprintln!("Failed to launch game: {}", e);
@@ -228,6 +251,8 @@ fn xmain() -> i32 {
             },
         };
 
+    components.fps_cap = fps_cap;
+
     let time_now = gml::datetime::now_as_nanos();
 
     if let Err(err) = if let Some(path) = project_path {
@@ -249,6 +274,22 @@ fn xmain() -> i32 {
             .collect::<Vec<_>>();
         let result = if let Some(replay) = replay {
             components.replay(replay, output_bin)
+        } else if headless {
+            components.spoofed_time_nanos = if spoof_time { Some(time_now) } else { None };
+            let start_seed = components.rand.seed();
+            let mut replay = Replay::new(time_now, start_seed);
+            match headless_frames {
+                Some(frames) => components.run_headless(frames, &mut replay).and_then(|()| match &output_bin {
+                    Some(bin) => {
+                        let render_state = components.renderer.state();
+                        SaveState::from(&mut components, replay, render_state)
+                            .save_to_file(bin, &mut savestate::Buffer::new())
+                            .map_err(|e| format!("Error saving to {:?}: {:?}", bin, e).into())
+                    },
+                    None => Ok(()),
+                }),
+                None => Err("headless mode needs a frame count (-F) or a replay file (-f)".into()),
+            }
         } else {
             components.spoofed_time_nanos = if spoof_time { Some(time_now) } else { None };
             components.run()
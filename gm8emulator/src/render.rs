@@ -1,6 +1,7 @@
 //! Game rendering functionality
 
 pub mod atlas;
+mod null;
 mod opengl;
 
 use crate::types::Colour;
@@ -20,6 +21,55 @@ pub enum Scaling {
     Full,
 }
 
+impl Scaling {
+    /// Gets the region of the window which a framebuffer of the given size is drawn to, as (x, y, w, h).
+    pub fn region(&self, fb_width: i32, fb_height: i32, window_width: i32, window_height: i32) -> (i32, i32, i32, i32) {
+        match *self {
+            Scaling::Fixed(scale) => {
+                let w = (f64::from(fb_width) * scale) as i32;
+                let h = (f64::from(fb_height) * scale) as i32;
+                ((window_width - w) / 2, (window_height - h) / 2, w, h)
+            },
+            Scaling::Aspect(_) => {
+                if fb_width > 0 && fb_height > 0 {
+                    let fixed_width = window_height * fb_width / fb_height;
+                    if fixed_width < window_width {
+                        // window is too wide
+                        ((window_width - fixed_width) / 2, 0, fixed_width, window_height)
+                    } else {
+                        // window is too tall
+                        let fixed_height = window_width * fb_height / fb_width;
+                        (0, (window_height - fixed_height) / 2, window_width, fixed_height)
+                    }
+                } else {
+                    // can never be too careful
+                    (0, 0, fb_width, fb_height)
+                }
+            },
+            Scaling::Full => (0, 0, window_width, window_height),
+        }
+    }
+
+    /// Maps a point in the window to the corresponding point on a framebuffer of the given size.
+    pub fn window_to_framebuffer(
+        &self,
+        x: i32,
+        y: i32,
+        fb_width: i32,
+        fb_height: i32,
+        window_width: i32,
+        window_height: i32,
+    ) -> (i32, i32) {
+        let (r_x, r_y, r_w, r_h) = self.region(fb_width, fb_height, window_width, window_height);
+        if r_w <= 0 || r_h <= 0 {
+            return (x, y)
+        }
+        let x = (f64::from(x - r_x) * f64::from(fb_width) / f64::from(r_w)).floor() as i32;
+        let y = (f64::from(y - r_y) * f64::from(fb_height) / f64::from(r_h)).floor() as i32;
+        (x, y)
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SavedTexture {
     width: i32,
@@ -514,6 +564,14 @@ pub trait RendererTrait {
     fn set_light(&mut self, id: usize, light: Light);
 }
 
+/// Which implementation a Renderer should use.
+pub enum Backend<'a> {
+    /// Draws to the given window with OpenGL.
+    OpenGL(&'a Window),
+    /// Draws nothing, and doesn't need a window. Used for headless runs.
+    Null,
+}
+
 pub struct RendererOptions {
     pub size: (u32, u32),
     pub vsync: bool,
@@ -535,10 +593,11 @@ impl Default for RendererOptions {
 }
 
 impl Renderer {
-    pub fn new(backend: (), options: &RendererOptions, window: &Window, clear_colour: Colour) -> Result<Self, String> {
-        Ok(Self(Box::new(match backend {
-            () => opengl::RendererImpl::new(options, window, clear_colour)?,
-        })))
+    pub fn new(backend: Backend, options: &RendererOptions, clear_colour: Colour) -> Result<Self, String> {
+        Ok(Self(match backend {
+            Backend::OpenGL(window) => Box::new(opengl::RendererImpl::new(options, window, clear_colour)?),
+            Backend::Null => Box::new(null::RendererImpl::new(options)),
+        }))
     }
 
     pub fn max_texture_size(&self) -> u32 {
@@ -1178,6 +1237,15 @@ pub struct RendererState {
     pub zbuf_trashed: bool,
 }
 
+fn split_colour(rgb: i32, alpha: f64) -> [f32; 4] {
+    [
+        ((rgb & 0xFF) as f32) / 255.0,
+        (((rgb >> 8) & 0xFF) as f32) / 255.0,
+        (((rgb >> 16) & 0xFF) as f32) / 255.0,
+        alpha.max(0.0).min(1.0) as f32,
+    ]
+}
+
 /// Multiply two mat4's together
 fn mat4mult(m1: [f32; 16], m2: [f32; 16]) -> [f32; 16] {
     [
@@ -1199,3 +1267,25 @@ fn mat4mult(m1: [f32; 16], m2: [f32; 16]) -> [f32; 16] {
         (m1[12] * m2[3]) + (m1[13] * m2[7]) + (m1[14] * m2[11]) + (m1[15] * m2[15]),
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaling_region() {
+        assert_eq!(Scaling::Full.region(640, 480, 1000, 500), (0, 0, 1000, 500));
+        assert_eq!(Scaling::Fixed(2.0).region(320, 240, 800, 600), (80, 60, 640, 480));
+        assert_eq!(Scaling::Aspect(1.0).region(640, 480, 1000, 480), (180, 0, 640, 480));
+        assert_eq!(Scaling::Aspect(1.0).region(640, 480, 640, 800), (0, 160, 640, 480));
+    }
+
+    #[test]
+    fn scaling_window_to_framebuffer() {
+        let aspect = Scaling::Aspect(1.0);
+        assert_eq!(aspect.window_to_framebuffer(180, 0, 640, 480, 1000, 480), (0, 0));
+        assert_eq!(aspect.window_to_framebuffer(500, 240, 640, 480, 1000, 480), (320, 240));
+        assert_eq!(aspect.window_to_framebuffer(100, 10, 640, 480, 1000, 480), (-80, 10));
+        assert_eq!(Scaling::Full.window_to_framebuffer(500, 250, 640, 480, 1000, 500), (320, 240));
+    }
+}
@@ -0,0 +1,899 @@
+use crate::{
+    render::{
+        atlas::{AtlasBuilder, AtlasRect, AtlasRef},
+        mat4mult, split_colour, BlendType, Fog, Light, PrimitiveBuilder, PrimitiveType, RendererOptions, RendererTrait,
+        SavedTexture, Scaling, VertexBuffer,
+    },
+    types::Colour,
+};
+use std::{any::Any, cell::Cell, convert::TryInto};
+
+/// Texture size reported to the atlas builder. There's no GPU to ask, so pick something every GPU supports.
+const MAX_TEXTURE_SIZE: u32 = 8192;
+
+/// An RGBA image standing in for a GPU texture.
+struct Texture {
+    width: i32,
+    height: i32,
+    pixels: Box<[u8]>,
+    has_zbuf: bool,
+}
+
+impl Texture {
+    fn new(width: i32, height: i32, has_zbuf: bool) -> Self {
+        Self { width, height, pixels: vec![0; (width * height * 4).max(0) as usize].into_boxed_slice(), has_zbuf }
+    }
+
+    /// Reads a region of the texture, with anything out of bounds left as zeros.
+    fn read(&self, x: i32, y: i32, w: i32, h: i32) -> Box<[u8]> {
+        let (w, h) = (w.max(0), h.max(0));
+        let mut data = vec![0; (w * h * 4) as usize].into_boxed_slice();
+        let (x1, x2) = (x.max(0), (x + w).min(self.width));
+        if x1 < x2 {
+            for row in y.max(0)..(y + h).min(self.height) {
+                let src = ((row * self.width + x1) * 4) as usize..((row * self.width + x2) * 4) as usize;
+                let dst = (((row - y) * w + x1 - x) * 4) as usize;
+                if let Some(src) = self.pixels.get(src) {
+                    data[dst..dst + src.len()].copy_from_slice(src);
+                }
+            }
+        }
+        data
+    }
+
+    /// Writes a region of the texture, skipping anything out of bounds.
+    fn write(&mut self, x: i32, y: i32, w: i32, h: i32, data: &[u8]) {
+        let (x1, x2) = (x.max(0), (x + w).min(self.width));
+        if x1 < x2 {
+            for row in y.max(0)..(y + h).min(self.height) {
+                let src = (((row - y) * w + x1 - x) * 4) as usize..(((row - y) * w + x2 - x) * 4) as usize;
+                let dst = ((row * self.width + x1) * 4) as usize;
+                if let (Some(src), Some(dst)) = (data.get(src), self.pixels.get_mut(dst..)) {
+                    dst[..src.len()].copy_from_slice(src);
+                }
+            }
+        }
+    }
+}
+
+/// A renderer which keeps track of all the state a game can observe, but never draws anything.
+/// Used for headless runs, where there's no window or graphics context to draw to.
+/// Sprite and surface pixels are kept so they can be dumped and saved, but drawing never changes them.
+pub struct RendererImpl {
+    // indexed by atlas id, stock atlases have no pixels as each stock texture is kept in stock_textures
+    atlases: Vec<Option<Texture>>,
+    stock_textures: Vec<Texture>,
+    texture_rects: Vec<Option<AtlasRect>>,
+    stock_texture_count: usize,
+    stock_atlas_count: u32,
+    framebuffer_size: (u32, u32),
+    stored_size: Option<(u32, u32)>,
+    zbuf_trashed: bool,
+    white_pixel: AtlasRect,
+    vsync: Cell<bool>,
+
+    model_matrix: [f32; 16],
+    alpha_blending: bool,
+    blend_mode: (BlendType, BlendType),
+    interpolate_pixels: bool,
+    texture_repeat: bool,
+    depth_test: bool,
+    write_depth: bool,
+    culling: bool,
+    fog: Option<Fog>,
+    gouraud: bool,
+    lighting: bool,
+    ambient_colour: i32,
+    lights: [(bool, Light); 8],
+    circle_precision: i32,
+    using_3d: bool,
+    perspective: bool,
+    depth: f32,
+    primitive_2d: PrimitiveBuilder,
+    primitive_3d: PrimitiveBuilder,
+}
+
+impl RendererImpl {
+    pub fn new(options: &RendererOptions) -> Self {
+        #[rustfmt::skip]
+        let identity_matrix: [f32; 16] = [
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ];
+        let no_light = (false, Light::Directional { direction: [0.0; 3], colour: 0 });
+        Self {
+            atlases: Vec::new(),
+            stock_textures: Vec::new(),
+            texture_rects: Vec::new(),
+            stock_texture_count: 0,
+            stock_atlas_count: 0,
+            framebuffer_size: options.size,
+            stored_size: None,
+            zbuf_trashed: false,
+            white_pixel: Default::default(),
+            vsync: Cell::new(options.vsync),
+            model_matrix: identity_matrix,
+            alpha_blending: true,
+            blend_mode: (BlendType::SrcAlpha, BlendType::InvSrcAlpha),
+            interpolate_pixels: options.interpolate_pixels,
+            texture_repeat: false,
+            depth_test: false,
+            write_depth: false,
+            culling: false,
+            fog: None,
+            gouraud: true,
+            lighting: false,
+            ambient_colour: 0,
+            lights: [no_light; 8],
+            circle_precision: 24,
+            using_3d: false,
+            perspective: false,
+            depth: 0.0,
+            primitive_2d: PrimitiveBuilder::new(Default::default(), PrimitiveType::PointList),
+            primitive_3d: PrimitiveBuilder::new(Default::default(), PrimitiveType::PointList),
+        }
+    }
+
+    fn get_rect_mut(&mut self, id: AtlasRef) -> Option<&mut AtlasRect> {
+        id.0.try_into()
+            .ok()
+            .and_then(move |id: usize| self.texture_rects.get_mut(id))
+            .and_then(|o: &mut Option<AtlasRect>| o.as_mut())
+    }
+
+    /// Writes pixels to part of a sprite. Stock sprites can't be written to.
+    fn write_sprite(&mut self, atlas_ref: AtlasRef, x: i32, y: i32, w: i32, h: i32, data: &[u8]) {
+        if (atlas_ref.0 as usize) < self.stock_texture_count {
+            return
+        }
+        // every other sprite has its own atlas, so the atlas bounds are the sprite bounds
+        if let Some(rect) = self.get_rect(atlas_ref).copied() {
+            if let Some(Some(atlas)) = self.atlases.get_mut(rect.atlas_id as usize) {
+                atlas.write(rect.x + x, rect.y + y, w, h, data);
+            }
+        }
+    }
+}
+
+impl RendererTrait for RendererImpl {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn max_texture_size(&self) -> u32 {
+        MAX_TEXTURE_SIZE
+    }
+
+    fn push_atlases(&mut self, mut atl: AtlasBuilder) -> Result<(), String> {
+        assert!(self.atlases.is_empty(), "atlases should be initialized only once");
+        let white_pixel_ref =
+            atl.texture(1, 1, 0, 0, Box::new([0xFF, 0xFF, 0xFF, 0xFF])).ok_or("Couldn't pack white_pixel")?;
+        let (packers, mut sprites) = atl.into_inner();
+        self.white_pixel = sprites[white_pixel_ref.0 as usize].0;
+        self.reset_primitive_2d(PrimitiveType::PointList, None);
+        self.reset_primitive_3d(PrimitiveType::PointList, None);
+
+        self.atlases = packers
+            .iter()
+            .map(|p| {
+                let (width, height) = p.size();
+                Some(Texture { width, height, pixels: Box::new([]), has_zbuf: false })
+            })
+            .collect();
+        // keep atlas ids in line with the OpenGL backend, so savestates are interchangeable
+        self.stock_atlas_count = packers.len() as u32 + 2;
+
+        // stock pixels are BGRA, but everything is dumped as RGBA
+        self.stock_textures = sprites
+            .iter()
+            .map(|(ar, pixels)| {
+                let mut pixels = pixels.clone();
+                pixels.chunks_exact_mut(4).for_each(|px| px.swap(0, 2));
+                Texture { width: ar.w, height: ar.h, pixels, has_zbuf: false }
+            })
+            .collect();
+        self.texture_rects = sprites.drain(..).map(|(ar, _)| Some(ar)).collect();
+        self.stock_texture_count = self.texture_rects.len();
+        Ok(())
+    }
+
+    fn upload_sprite(
+        &mut self,
+        data: Box<[u8]>,
+        width: i32,
+        height: i32,
+        origin_x: i32,
+        origin_y: i32,
+    ) -> Result<AtlasRef, String> {
+        let atlas_ref = self.create_surface(width, height, false)?;
+        if let Some(rect) = self.get_rect_mut(atlas_ref) {
+            rect.origin_x = origin_x as f32 / width as f32;
+            rect.origin_y = origin_y as f32 / height as f32;
+            self.write_sprite(atlas_ref, 0, 0, width, height, &data);
+        }
+        Ok(atlas_ref)
+    }
+
+    fn duplicate_sprite(&mut self, atlas_ref: AtlasRef) -> Result<AtlasRef, String> {
+        if let Some(rect) = self.get_rect(atlas_ref).cloned() {
+            let data = self.dump_sprite(atlas_ref);
+            let sprite = self.create_surface(rect.w, rect.h, false)?;
+            let new_rect = self.get_rect_mut(sprite).unwrap();
+            new_rect.origin_x = rect.origin_x;
+            new_rect.origin_y = rect.origin_y;
+            self.write_sprite(sprite, 0, 0, rect.w, rect.h, &data);
+            Ok(sprite)
+        } else {
+            Ok(AtlasRef(-1))
+        }
+    }
+
+    fn delete_sprite(&mut self, atlas_ref: AtlasRef) {
+        if let Some(rect) = atlas_ref
+            .0
+            .try_into()
+            .ok()
+            .and_then(|id: usize| self.texture_rects.get_mut(id))
+            .and_then(|o: &mut Option<AtlasRect>| o.take())
+        {
+            if rect.atlas_id >= self.stock_atlas_count {
+                self.atlases[rect.atlas_id as usize] = None;
+            }
+        }
+    }
+
+    fn resize_framebuffer(&mut self, width: u32, height: u32, store: bool) {
+        if store {
+            self.stored_size = Some(self.framebuffer_size);
+        } else {
+            self.stored_size = None;
+        }
+        self.framebuffer_size = (width, height);
+    }
+
+    fn set_vsync(&self, vsync: bool) {
+        self.vsync.set(vsync);
+    }
+
+    fn get_vsync(&self) -> bool {
+        self.vsync.get()
+    }
+
+    fn wait_vsync(&self) {}
+
+    fn get_rect(&self, id: AtlasRef) -> Option<&AtlasRect> {
+        id.0.try_into()
+            .ok()
+            .and_then(|id: usize| self.texture_rects.get(id))
+            .and_then(|o: &Option<AtlasRect>| o.as_ref())
+    }
+
+    fn draw_sprite_general(
+        &mut self,
+        _texture: AtlasRef,
+        _part_x: f64,
+        _part_y: f64,
+        _part_w: f64,
+        _part_h: f64,
+        _x: f64,
+        _y: f64,
+        _xscale: f64,
+        _yscale: f64,
+        _angle: f64,
+        _col1: i32,
+        _col2: i32,
+        _col3: i32,
+        _col4: i32,
+        _alpha: f64,
+        _use_origin: bool,
+    ) {
+    }
+
+    fn set_view_matrix(&mut self, _view: [f32; 16]) {}
+
+    fn set_viewproj_matrix(&mut self, _view: [f32; 16], _proj: [f32; 16]) {}
+
+    fn get_model_matrix(&self) -> [f32; 16] {
+        self.model_matrix
+    }
+
+    fn set_model_matrix(&mut self, model: [f32; 16]) {
+        self.model_matrix = model;
+    }
+
+    fn mult_model_matrix(&mut self, model: [f32; 16]) {
+        self.model_matrix = mat4mult(self.model_matrix, model);
+    }
+
+    fn set_projection_ortho(&mut self, _x: f64, _y: f64, _w: f64, _h: f64, _angle: f64) {}
+
+    fn set_projection_perspective(&mut self, _x: f64, _y: f64, _w: f64, _h: f64, _angle: f64) {}
+
+    fn set_view(
+        &mut self,
+        _src_x: i32,
+        _src_y: i32,
+        _src_w: i32,
+        _src_h: i32,
+        _src_angle: f64,
+        _port_x: i32,
+        _port_y: i32,
+        _port_w: i32,
+        _port_h: i32,
+    ) {
+    }
+
+    fn flush_queue(&mut self) {}
+
+    fn present(&mut self, _window_width: u32, _window_height: u32, _scaling: Scaling) {}
+
+    fn draw_stored(&mut self, _target_x: i32, _target_y: i32, _width: u32, _height: u32) {}
+
+    fn stored_size(&self) -> (u32, u32) {
+        self.stored_size.unwrap_or(self.framebuffer_size)
+    }
+
+    fn finish(&mut self, _window_width: u32, _window_height: u32, _clear_colour: Colour) {}
+
+    fn dump_sprite_part(&self, texture: AtlasRef, part_x: i32, part_y: i32, part_w: i32, part_h: i32) -> Box<[u8]> {
+        let rect = match self.get_rect(texture) {
+            Some(rect) => rect,
+            None => return Box::new([]),
+        };
+        if (texture.0 as usize) < self.stock_texture_count {
+            self.stock_textures[texture.0 as usize].read(part_x, part_y, part_w, part_h)
+        } else {
+            match self.atlases.get(rect.atlas_id as usize) {
+                Some(Some(atlas)) => atlas.read(rect.x + part_x, rect.y + part_y, part_w, part_h),
+                _ => vec![0; (part_w * part_h * 4).max(0) as usize].into_boxed_slice(),
+            }
+        }
+    }
+
+    fn get_alpha_blending(&self) -> bool {
+        self.alpha_blending
+    }
+
+    fn set_alpha_blending(&mut self, alphablend: bool) {
+        self.alpha_blending = alphablend;
+    }
+
+    fn get_blend_mode(&self) -> (BlendType, BlendType) {
+        self.blend_mode
+    }
+
+    fn set_blend_mode(&mut self, src: BlendType, dst: BlendType) {
+        self.blend_mode = (src, dst);
+    }
+
+    fn get_pixel_interpolation(&self) -> bool {
+        self.interpolate_pixels
+    }
+
+    fn set_pixel_interpolation(&mut self, lerping: bool) {
+        self.interpolate_pixels = lerping;
+    }
+
+    fn get_texture_repeat(&self) -> bool {
+        self.texture_repeat
+    }
+
+    fn set_texture_repeat(&mut self, repeat: bool) {
+        self.texture_repeat = repeat;
+    }
+
+    fn get_pixels(&self, _x: i32, _y: i32, w: i32, h: i32) -> Box<[u8]> {
+        vec![0; (w * h * 4).max(0) as usize].into_boxed_slice()
+    }
+
+    fn stored_pixels(&self) -> Box<[u8]> {
+        let (width, height) = self.stored_size();
+        vec![0; (width * height * 4) as usize].into_boxed_slice()
+    }
+
+    fn stored_zbuffer(&self) -> Box<[f32]> {
+        let (width, height) = self.stored_size();
+        vec![0.0; (width * height) as usize].into_boxed_slice()
+    }
+
+    fn set_stored(&mut self, _rgba: Box<[u8]>, _zbuf: Box<[f32]>, fb_w: u32, fb_h: u32) {
+        self.stored_size = Some((fb_w, fb_h));
+    }
+
+    fn dump_dynamic_textures(&self) -> Vec<Option<SavedTexture>> {
+        self.atlases
+            .iter()
+            .skip(self.stock_atlas_count as usize)
+            .map(|atlas| {
+                atlas.as_ref().map(|atlas| SavedTexture {
+                    width: atlas.width,
+                    height: atlas.height,
+                    pixels: atlas.pixels.clone(),
+                    zbuf: if atlas.has_zbuf {
+                        Some(vec![0.0; (atlas.width * atlas.height) as usize].into_boxed_slice())
+                    } else {
+                        None
+                    },
+                })
+            })
+            .collect()
+    }
+
+    fn upload_dynamic_textures(&mut self, textures: &[Option<SavedTexture>]) {
+        self.atlases.truncate(self.stock_atlas_count as usize);
+        self.atlases.resize_with(self.stock_atlas_count as usize, || None);
+        self.atlases.extend(textures.iter().map(|t| {
+            t.as_ref().map(|t| Texture {
+                width: t.width,
+                height: t.height,
+                pixels: t.pixels.clone(),
+                has_zbuf: t.zbuf.is_some(),
+            })
+        }));
+    }
+
+    fn create_sprite_colour(&mut self, width: i32, height: i32, col: Colour) -> Result<AtlasRef, String> {
+        let atlas_ref = self.create_surface(width, height, false)?;
+        let pixel = [(col.r * 255.0).round() as u8, (col.g * 255.0).round() as u8, (col.b * 255.0).round() as u8, 255];
+        let data = pixel.repeat((width * height).max(0) as usize);
+        self.write_sprite(atlas_ref, 0, 0, width, height, &data);
+        Ok(atlas_ref)
+    }
+
+    fn create_surface(&mut self, width: i32, height: i32, has_zbuffer: bool) -> Result<AtlasRef, String> {
+        let atlas_id = if let Some(id) = self.atlases.iter().position(|x| x.is_none()) {
+            id as u32
+        } else {
+            self.atlases.push(None);
+            self.atlases.len() as u32 - 1
+        };
+        self.atlases[atlas_id as usize] = Some(Texture::new(width, height, has_zbuffer));
+        let id = self.texture_rects.len() as i32;
+        self.texture_rects.push(Some(AtlasRect {
+            atlas_id,
+            x: 0,
+            y: 0,
+            w: width,
+            h: height,
+            origin_x: 0.0,
+            origin_y: 0.0,
+        }));
+        Ok(AtlasRef(id))
+    }
+
+    fn set_target(&mut self, _atlas_ref: AtlasRef) {}
+
+    fn reset_target(&mut self) {}
+
+    fn copy_surface(
+        &mut self,
+        dest: AtlasRef,
+        dest_x: i32,
+        dest_y: i32,
+        src: AtlasRef,
+        src_x: i32,
+        src_y: i32,
+        width: i32,
+        height: i32,
+    ) {
+        // only copy what's inside the source, like OpenGL does
+        let (src_w, src_h) = match self.get_rect(src) {
+            Some(rect) => (rect.w, rect.h),
+            None => return,
+        };
+        let (x1, y1) = (src_x.max(0), src_y.max(0));
+        let (x2, y2) = ((src_x + width).min(src_w), (src_y + height).min(src_h));
+        if x1 < x2 && y1 < y2 {
+            let data = self.dump_sprite_part(src, x1, y1, x2 - x1, y2 - y1);
+            self.write_sprite(dest, dest_x + x1 - src_x, dest_y + y1 - src_y, x2 - x1, y2 - y1, &data);
+        }
+    }
+
+    fn set_zbuf_trashed(&mut self, trashed: bool) {
+        self.zbuf_trashed = trashed;
+    }
+
+    fn get_zbuf_trashed(&self) -> bool {
+        self.zbuf_trashed
+    }
+
+    fn get_texture_id(&mut self, atl_ref: AtlasRef) -> i32 {
+        atl_ref.0
+    }
+
+    fn get_texture_from_id(&self, id: i32) -> Option<AtlasRef> {
+        Some(AtlasRef(id))
+    }
+
+    fn get_texture_rects(&self) -> Vec<Option<AtlasRect>> {
+        self.texture_rects[self.stock_texture_count..].to_vec()
+    }
+
+    fn set_texture_rects(&mut self, rects: &[Option<AtlasRect>]) {
+        self.texture_rects.truncate(self.stock_texture_count);
+        self.texture_rects.extend_from_slice(rects);
+    }
+
+    fn draw_rectangle(&mut self, _x1: f64, _y1: f64, _x2: f64, _y2: f64, _colour: i32, _alpha: f64) {}
+
+    fn draw_rectangle_outline(&mut self, _x1: f64, _y1: f64, _x2: f64, _y2: f64, _colour: i32, _alpha: f64) {}
+
+    fn draw_rectangle_gradient(
+        &mut self,
+        _x1: f64,
+        _y1: f64,
+        _x2: f64,
+        _y2: f64,
+        _c1: i32,
+        _c2: i32,
+        _c3: i32,
+        _c4: i32,
+        _alpha: f64,
+        _outline: bool,
+    ) {
+    }
+
+    fn draw_point(&mut self, _x: f64, _y: f64, _colour: i32, _alpha: f64) {}
+
+    fn draw_line(
+        &mut self,
+        _x1: f64,
+        _y1: f64,
+        _x2: f64,
+        _y2: f64,
+        _width: Option<f64>,
+        _c1: i32,
+        _c2: i32,
+        _alpha: f64,
+    ) {
+    }
+
+    fn draw_triangle(
+        &mut self,
+        _x1: f64,
+        _y1: f64,
+        _x2: f64,
+        _y2: f64,
+        _x3: f64,
+        _y3: f64,
+        _c1: i32,
+        _c2: i32,
+        _c3: i32,
+        _alpha: f64,
+        _outline: bool,
+    ) {
+    }
+
+    fn draw_ellipse(
+        &mut self,
+        _x: f64,
+        _y: f64,
+        _rad_x: f64,
+        _rad_y: f64,
+        _c1: i32,
+        _c2: i32,
+        _alpha: f64,
+        _outline: bool,
+    ) {
+    }
+
+    fn draw_roundrect(
+        &mut self,
+        _x1: f64,
+        _y1: f64,
+        _x2: f64,
+        _y2: f64,
+        _c1: i32,
+        _c2: i32,
+        _alpha: f64,
+        _outline: bool,
+    ) {
+    }
+
+    fn set_circle_precision(&mut self, prec: i32) {
+        self.circle_precision = (prec.max(4).min(64) >> 2) << 2;
+    }
+
+    fn get_circle_precision(&self) -> i32 {
+        self.circle_precision
+    }
+
+    fn reset_primitive_2d(&mut self, ptype: PrimitiveType, atlas_ref: Option<AtlasRef>) {
+        self.primitive_2d = PrimitiveBuilder::new(
+            atlas_ref.and_then(|ar| self.get_rect(ar).copied()).unwrap_or(self.white_pixel),
+            ptype,
+        );
+    }
+
+    fn vertex_2d(&mut self, x: f64, y: f64, xtex: f64, ytex: f64, col: i32, alpha: f64) {
+        self.primitive_2d.push_vertex(
+            [x as f32, y as f32, self.depth],
+            [xtex as f32, ytex as f32],
+            split_colour(col, alpha),
+            [0.0, 0.0, 0.0],
+        );
+    }
+
+    fn draw_primitive_2d(&mut self) {}
+
+    fn get_primitive_2d(&self) -> PrimitiveBuilder {
+        self.primitive_2d.clone()
+    }
+
+    fn set_primitive_2d(&mut self, prim: PrimitiveBuilder) {
+        self.primitive_2d = prim;
+    }
+
+    fn reset_primitive_3d(&mut self, ptype: PrimitiveType, atlas_ref: Option<AtlasRef>) {
+        self.primitive_3d = PrimitiveBuilder::new(
+            atlas_ref.and_then(|ar| self.get_rect(ar).copied()).unwrap_or(self.white_pixel),
+            ptype,
+        );
+    }
+
+    fn vertex_3d(
+        &mut self,
+        x: f64,
+        y: f64,
+        z: f64,
+        nx: f64,
+        ny: f64,
+        nz: f64,
+        xtex: f64,
+        ytex: f64,
+        col: i32,
+        alpha: f64,
+    ) {
+        self.primitive_3d.push_vertex(
+            [x as f32, y as f32, z as f32],
+            [xtex as f32, ytex as f32],
+            split_colour(col, alpha),
+            [nx as f32, ny as f32, nz as f32],
+        );
+    }
+
+    fn draw_primitive_3d(&mut self) {}
+
+    fn get_primitive_3d(&self) -> PrimitiveBuilder {
+        self.primitive_3d.clone()
+    }
+
+    fn set_primitive_3d(&mut self, prim: PrimitiveBuilder) {
+        self.primitive_3d = prim;
+    }
+
+    fn extend_buffers(&self, _buf: &mut VertexBuffer) {}
+
+    fn draw_buffers(&mut self, _atlas_ref: Option<AtlasRef>, _buf: &VertexBuffer) {}
+
+    fn clear_view(&mut self, _colour: Colour, _alpha: f64) {}
+
+    fn clear_view_no_zbuf(&mut self, _colour: Colour, _alpha: f64) {}
+
+    fn clear_zbuf(&mut self) {}
+
+    fn get_3d(&self) -> bool {
+        self.using_3d
+    }
+
+    fn set_3d(&mut self, use_3d: bool) {
+        self.using_3d = use_3d;
+        self.set_depth_test(use_3d);
+        self.set_perspective(use_3d);
+    }
+
+    fn get_depth(&self) -> f32 {
+        self.depth
+    }
+
+    fn set_depth(&mut self, depth: f32) {
+        self.depth = if self.using_3d { depth.max(-16000.0).min(16000.0) } else { 0.0 };
+    }
+
+    fn get_depth_test(&self) -> bool {
+        self.depth_test
+    }
+
+    fn set_depth_test(&mut self, depth_test: bool) {
+        self.depth_test = depth_test && self.using_3d;
+    }
+
+    fn get_write_depth(&self) -> bool {
+        self.write_depth
+    }
+
+    fn set_write_depth(&mut self, write_depth: bool) {
+        self.write_depth = write_depth;
+    }
+
+    fn get_culling(&self) -> bool {
+        self.culling
+    }
+
+    fn set_culling(&mut self, culling: bool) {
+        self.culling = culling;
+    }
+
+    fn get_perspective(&self) -> bool {
+        self.perspective
+    }
+
+    fn set_perspective(&mut self, perspective: bool) {
+        self.perspective = perspective;
+    }
+
+    fn get_fog(&self) -> Option<Fog> {
+        self.fog.clone()
+    }
+
+    fn set_fog(&mut self, fog: Option<Fog>) {
+        self.fog = fog;
+    }
+
+    fn get_gouraud(&self) -> bool {
+        self.gouraud
+    }
+
+    fn set_gouraud(&mut self, gouraud: bool) {
+        self.gouraud = gouraud;
+    }
+
+    fn get_lighting_enabled(&self) -> bool {
+        self.lighting
+    }
+
+    fn set_lighting_enabled(&mut self, enabled: bool) {
+        self.lighting = enabled;
+    }
+
+    fn get_ambient_colour(&self) -> i32 {
+        self.ambient_colour
+    }
+
+    fn set_ambient_colour(&mut self, colour: i32) {
+        self.ambient_colour = colour;
+    }
+
+    fn get_lights(&self) -> [(bool, Light); 8] {
+        self.lights
+    }
+
+    fn set_lights(&mut self, lights: [(bool, Light); 8]) {
+        self.lights = lights;
+    }
+
+    fn set_light_enabled(&mut self, id: usize, enabled: bool) {
+        self.lights[id].0 = enabled;
+    }
+
+    fn set_light(&mut self, id: usize, light: Light) {
+        self.lights[id].1 = light;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn renderer() -> RendererImpl {
+        let mut renderer = RendererImpl::new(&Default::default());
+        renderer.push_atlases(AtlasBuilder::new(1024)).unwrap();
+        renderer
+    }
+
+    // the TAS UI uploads two sprites before the game does anything, taking the last two stock atlas ids
+    fn renderer_with_ui() -> RendererImpl {
+        let mut renderer = renderer();
+        for _ in 0..2 {
+            renderer.upload_sprite(Box::new([0, 0, 0, 0]), 1, 1, 0, 0).unwrap();
+        }
+        renderer
+    }
+
+    fn pixels(count: u8) -> Box<[u8]> {
+        (0..count * 4).collect::<Vec<_>>().into_boxed_slice()
+    }
+
+    #[test]
+    fn dump_edge_pixel() {
+        let mut renderer = renderer();
+        let surf = renderer.create_surface(4, 3, false).unwrap();
+        assert_eq!(renderer.dump_sprite_part(surf, 3, 2, 1, 1).len(), 4);
+        assert_eq!(renderer.dump_sprite(surf).len(), 4 * 3 * 4);
+    }
+
+    #[test]
+    fn upload_dump() {
+        let mut renderer = renderer_with_ui();
+        let sprite = renderer.upload_sprite(pixels(6), 3, 2, 1, 1).unwrap();
+        assert_eq!(renderer.dump_sprite(sprite), pixels(6));
+        assert_eq!(&*renderer.dump_sprite_part(sprite, 1, 1, 2, 1), &pixels(6)[16..24]);
+
+        // out of bounds pixels come back as zeros
+        let part = renderer.dump_sprite_part(sprite, 2, 1, 2, 2);
+        assert_eq!(&part[..4], &pixels(6)[20..24]);
+        assert!(part[4..].iter().all(|&x| x == 0));
+
+        let dupe = renderer.duplicate_sprite(sprite).unwrap();
+        assert_eq!(renderer.dump_sprite(dupe), pixels(6));
+    }
+
+    #[test]
+    fn stock_dump() {
+        let mut renderer = RendererImpl::new(&Default::default());
+        let mut atlases = AtlasBuilder::new(1024);
+        let sprite = atlases.texture(2, 1, 0, 0, Box::new([1, 2, 3, 4, 5, 6, 7, 8])).unwrap();
+        renderer.push_atlases(atlases).unwrap();
+        assert_eq!(&*renderer.dump_sprite(sprite), &[3, 2, 1, 4, 7, 6, 5, 8]);
+        assert_eq!(&*renderer.dump_sprite_part(sprite, 1, 0, 1, 1), &[7, 6, 5, 8]);
+    }
+
+    #[test]
+    fn copy_surface() {
+        let mut renderer = renderer_with_ui();
+        let src = renderer.upload_sprite(pixels(4), 2, 2, 0, 0).unwrap();
+        let dest = renderer.create_sprite_colour(2, 2, Colour::new(1.0, 0.0, 0.0)).unwrap();
+        renderer.copy_surface(dest, 0, 0, src, -1, 0, 2, 2);
+        #[rustfmt::skip]
+        assert_eq!(&*renderer.dump_sprite(dest), &[
+            255, 0, 0, 255, 0, 1, 2, 3,
+            255, 0, 0, 255, 8, 9, 10, 11,
+        ]);
+    }
+
+    #[test]
+    fn surface_create_free() {
+        let mut renderer = renderer_with_ui();
+        let stock_atlases = renderer.atlases.len();
+        assert_eq!(stock_atlases, renderer.stock_atlas_count as usize);
+
+        let first = renderer.create_surface(4, 4, false).unwrap();
+        let second = renderer.create_surface(8, 8, true).unwrap();
+        let first_atlas = renderer.get_rect(first).unwrap().atlas_id;
+        assert_eq!(first_atlas as usize, stock_atlases);
+        assert_eq!(renderer.atlases.len(), stock_atlases + 2);
+
+        // freed atlases are reused, but the sprite gets a new id
+        renderer.delete_sprite(first);
+        assert!(renderer.get_rect(first).is_none());
+        assert!(renderer.atlases[first_atlas as usize].is_none());
+        let third = renderer.create_surface(2, 2, false).unwrap();
+        assert_ne!(third.0, first.0);
+        assert_eq!(renderer.get_rect(third).unwrap().atlas_id, first_atlas);
+        assert_eq!(renderer.atlases.len(), stock_atlases + 2);
+
+        // stock atlases are never freed
+        renderer.delete_sprite(AtlasRef(0));
+        assert!(renderer.atlases[0].is_some());
+        renderer.delete_sprite(second);
+        renderer.delete_sprite(third);
+        assert_eq!(renderer.atlases.iter().filter(|x| x.is_some()).count(), stock_atlases);
+    }
+
+    #[test]
+    fn dynamic_textures() {
+        let mut renderer = renderer_with_ui();
+        let sprite = renderer.upload_sprite(pixels(4), 2, 2, 0, 0).unwrap();
+        let deleted = renderer.create_surface(4, 4, false).unwrap();
+        let surf = renderer.create_surface(3, 1, true).unwrap();
+        renderer.delete_sprite(deleted);
+
+        let textures = renderer.dump_dynamic_textures();
+        let rects = renderer.get_texture_rects();
+        assert_eq!(textures.len(), 3);
+        assert!(textures[1].is_none());
+        assert!(textures[2].as_ref().unwrap().zbuf.is_some());
+
+        let mut loaded = renderer_with_ui();
+        loaded.create_surface(5, 5, false).unwrap();
+        loaded.set_texture_rects(&rects);
+        loaded.upload_dynamic_textures(&textures);
+        assert_eq!(loaded.atlases.len(), renderer.atlases.len());
+        assert_eq!(loaded.dump_sprite(sprite), pixels(4));
+        assert!(loaded.get_rect(deleted).is_none());
+        assert_eq!(loaded.dump_sprite(surf).len(), 3 * 4);
+        assert!(loaded.dump_dynamic_textures()[2].as_ref().unwrap().zbuf.is_some());
+    }
+}
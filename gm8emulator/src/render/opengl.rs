@@ -1,8 +1,8 @@
 use crate::{
     render::{
         atlas::{AtlasBuilder, AtlasRect, AtlasRef},
-        mat4mult, BlendType, Fog, Light, PrimitiveBuilder, PrimitiveShape, PrimitiveType, RendererOptions,
-        RendererTrait, SavedTexture, Scaling, Vertex, VertexBuffer,
+        mat4mult, split_colour, BlendType, Fog, Light, PrimitiveBuilder, PrimitiveShape, PrimitiveType,
+        RendererOptions, RendererTrait, SavedTexture, Scaling, Vertex, VertexBuffer,
     },
     types::Colour,
 };
@@ -249,15 +249,6 @@ fn make_view_matrix(x: f64, y: f64, z: f64, w: f64, h: f64, angle: f64) -> [f32;
     view_matrix
 }
 
-fn split_colour(rgb: i32, alpha: f64) -> [f32; 4] {
-    [
-        ((rgb & 0xFF) as f32) / 255.0,
-        (((rgb >> 8) & 0xFF) as f32) / 255.0,
-        (((rgb >> 16) & 0xFF) as f32) / 255.0,
-        alpha.max(0.0).min(1.0) as f32,
-    ]
-}
-
 // TODO: probably put this in render.rs instead
 impl VertexBuffer {
     pub fn swap_colour(&mut self, old: (i32, f64), new: (i32, f64)) {
@@ -1409,7 +1400,7 @@ impl RendererTrait for RendererImpl {
     fn dump_sprite_part(&self, atlas_ref: AtlasRef, part_x: i32, part_y: i32, part_w: i32, part_h: i32) -> Box<[u8]> {
         let rect = match self.get_rect(atlas_ref) {
             Some(rect) => {
-                AtlasRect { x: rect.x + part_x, y: rect.y + part_y, w: rect.w + part_w, h: rect.h + part_h, ..*rect }
+                AtlasRect { x: rect.x + part_x, y: rect.y + part_y, w: part_w, h: part_h, ..*rect }
             },
             None => return Box::new([]),
         };
@@ -2375,30 +2366,7 @@ impl RendererTrait for RendererImpl {
             let (window_width, window_height) = (window_width as i32, window_height as i32);
 
             // Scaling
-            let (w_x, w_y, w_w, w_h) = match scaling {
-                Scaling::Fixed(scale) => {
-                    let w = (f64::from(fb_width) * scale) as i32;
-                    let h = (f64::from(fb_height) * scale) as i32;
-                    ((window_width - w) / 2, (window_height - h) / 2, w, h)
-                },
-                Scaling::Aspect(_) => {
-                    if fb_width > 0 && fb_height > 0 {
-                        let fixed_width = window_height * fb_width / fb_height;
-                        if fixed_width < window_width {
-                            // window is too wide
-                            ((window_width - fixed_width) / 2, 0, fixed_width, window_height)
-                        } else {
-                            // window is too tall
-                            let fixed_height = window_width * fb_height / fb_width;
-                            (0, (window_height - fixed_height) / 2, window_width, fixed_height)
-                        }
-                    } else {
-                        // can never be too careful
-                        (0, 0, fb_width, fb_height)
-                    }
-                },
-                Scaling::Full => (0, 0, window_width, window_height),
-            };
+            let (w_x, w_y, w_w, w_h) = scaling.region(fb_width, fb_height, window_width, window_height);
 
             // On Intel, glBlitFrameBuffer just does nothing if the scissor box is too big, which it
             // very well could be. So just disable the scissor test for now.